@@ -0,0 +1,64 @@
+// Times a step of a 1000x1000 soup with each way `Universe` has of taking
+// one, and counts the allocations a step makes once the spare buffer and
+// caches have grown to size. Run with `cargo bench`.
+use life::Universe;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+const SIZE: u32 = 1000;
+const STEPS: u32 = 50;
+
+// the system allocator, counting every allocation and reallocation
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn time<F: FnMut(&mut Universe)>(name: &str, mut step: F) {
+    let mut universe = Universe::new_random(SIZE, SIZE, 0.3, 42);
+    for _ in 0..5 {
+        step(&mut universe);
+    }
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..STEPS {
+        step(&mut universe);
+    }
+    let per_step = start.elapsed().as_secs_f64() / STEPS as f64;
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    println!(
+        "{:<10} {:>9.3} ms/step {:>8.1} allocations/step",
+        name,
+        per_step * 1000.0,
+        allocations as f64 / STEPS as f64
+    );
+}
+
+fn main() {
+    println!("{}x{} soup, {} steps", SIZE, SIZE, STEPS);
+    time("update", |universe| {
+        universe.update();
+    });
+    time("parallel", |universe| {
+        universe.update_parallel();
+    });
+}
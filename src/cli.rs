@@ -0,0 +1,282 @@
+use crate::color::{parse_hex, Color};
+use crate::error::ParseError;
+use crate::game_of_life::Boundary;
+use crate::headless::Engine;
+use crate::rule::Rule;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+usage: life [options]
+       life bench [options]
+
+bench times --steps generations on --engine with nothing drawn and prints
+`<engine> <width> <height> <steps> <seconds> <generations per second>`.
+
+options:
+    --config <file>       read defaults from a TOML file (default life.toml, if
+                          it exists); flags override it
+    --width <pixels>      window width (default 1440)
+    --height <pixels>     window height (default 900)
+    --cells-x <cells>     board width (default half the window width)
+    --cells-y <cells>     board height (default half the window height)
+    --pattern <file>      start from a .rle, .lif/.life or .cells/.txt pattern,
+                          or from stdin for -
+    --stdin               read the pattern from stdin, like --pattern -
+    --image <file>        start from an image the size of the board, with pixels
+                          darker than --threshold alive
+    --threshold <n>       grayscale level from 0 to 255 (default 128)
+    --rule <rule>         B/S rule, like B36/S23 (default B3/S23)
+    --boundary <mode>     toroidal or dead (default toroidal)
+    --noise <p>           flip each cell with probability p after every
+                          generation, drawn from --seed (default 0)
+    --fg-color <color>    live cell color as #rgb or #rrggbb
+    --bg-color <color>    background color as #rgb or #rrggbb
+    --grid-color <color>  grid line color (G shows the grid) as #rgb or #rrggbb
+    --young-color <color> color of newborn cells when C shades cells by age
+    --old-color <color>   ...and of cells that have lived --max-age generations
+    --max-age <n>         generations until a cell is shaded --old-color
+                          (default 50)
+    --font <file>         TrueType font for the HUD instead of the bundled one
+    --renderer <name>     window or ascii, which draws in the terminal
+                          (default window)
+    --ups <n>             generations per second (default 30)
+    --max-fps <n>         most frames drawn per second (default 60)
+    --fade <frames>       fade dead cells out over this many frames (default 0,
+                          for none)
+    --record-scale <n>    pixels per cell in recordings made with R (default 2)
+    --record-delay <n>    hundredths of a second each generation is shown for
+                          in recordings (default 5)
+    --headless            run without a window and print the final state
+    --steps <n>           generations to run in headless mode (default 1000)
+    --dump-every <k>      in headless mode, write the board every k generations
+    --dump-file <file>    ...to this file, in plaintext under `# gen N` headers
+    --stats-csv <file>    in headless mode, write generation, population, births,
+                          deaths and density for every generation as CSV
+    --seed <n>            start from a random board made from this seed
+    --soup-search <n>     run n random soups, from seeds counting up from --seed,
+                          for up to --steps generations each, and print a
+                          histogram of what they settled into
+    --soup-size <cells>   width and height of each soup (default 16)
+    --engine <name>       sequential, parallel, packed, sparse or hashlife, for
+                          bench (default sequential)
+    --help                show this message";
+
+// What draws the board when it isn't headless.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Renderer {
+    // a Piston window
+    Window,
+    // the terminal, with ANSI escapes
+    Ascii,
+}
+
+impl std::str::FromStr for Renderer {
+    type Err = ParseError;
+
+    fn from_str(name: &str) -> Result<Renderer, ParseError> {
+        match name {
+            "window" => Ok(Renderer::Window),
+            "ascii" => Ok(Renderer::Ascii),
+            _ => Err(ParseError::new(format!("unknown renderer '{}'", name))),
+        }
+    }
+}
+
+// Command line options, parsed by hand to keep the dependencies down.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Options {
+    // TOML file with defaults for the options below
+    pub config: Option<PathBuf>,
+    // window size in window coordinates; the default is half of a
+    // 2880 x 1800 Retina laptop screen
+    pub width: f64,
+    pub height: f64,
+    // board size; unset means half the window size
+    pub cells_x: Option<u32>,
+    pub cells_y: Option<u32>,
+    // pattern file to start from instead of the default board; "-" for
+    // stdin
+    pub pattern: Option<PathBuf>,
+    // image to start from instead, with pixels darker than `threshold` alive
+    pub image: Option<PathBuf>,
+    pub threshold: u8,
+    // unset leaves the board's defaults
+    pub rule: Option<Rule>,
+    pub boundary: Option<Boundary>,
+    // chance of each cell flipping every generation
+    pub noise: Option<f64>,
+    // unset leaves the renderer's defaults
+    pub fg_color: Option<Color>,
+    pub bg_color: Option<Color>,
+    pub grid_color: Option<Color>,
+    // the ends of the age shading's gradient, and the age that reaches
+    // `old_color`
+    pub young_color: Option<Color>,
+    pub old_color: Option<Color>,
+    pub max_age: Option<u32>,
+    pub renderer: Renderer,
+    // simulation steps per second; unset leaves it to the renderer
+    pub ups: Option<f64>,
+    // frame rate cap; unset leaves Piston's default
+    pub max_fps: Option<u64>,
+    // frames a dead cell takes to fade out; unset for none
+    pub fade_frames: Option<u32>,
+    // pixels per cell and hundredths of a second per frame in recordings;
+    // unset leaves the renderer's defaults
+    pub record_scale: Option<u32>,
+    pub record_delay: Option<u16>,
+    // run the simulation without opening a window
+    pub headless: bool,
+    // generations to run in headless mode
+    pub steps: u64,
+    // in headless mode, write the board to `dump_file` every `dump_every`
+    // generations
+    pub dump_every: u64,
+    pub dump_file: Option<PathBuf>,
+    // in headless mode, write per-generation statistics here
+    pub stats_csv: Option<PathBuf>,
+    // time the simulation instead of running it
+    pub bench: bool,
+    pub engine: Engine,
+    // seed for a random starting board
+    pub seed: Option<u64>,
+    // soups to run instead of the simulation, and how big each is
+    pub soup_search: Option<u64>,
+    pub soup_size: u32,
+    // TrueType font for the HUD instead of the bundled one
+    pub font: Option<PathBuf>,
+    pub help: bool,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            config: None,
+            width: 1440.0,
+            height: 900.0,
+            cells_x: None,
+            cells_y: None,
+            pattern: None,
+            image: None,
+            threshold: 128,
+            rule: None,
+            boundary: None,
+            noise: None,
+            fg_color: None,
+            bg_color: None,
+            grid_color: None,
+            young_color: None,
+            old_color: None,
+            max_age: None,
+            renderer: Renderer::Window,
+            ups: None,
+            max_fps: None,
+            fade_frames: None,
+            record_scale: None,
+            record_delay: None,
+            headless: false,
+            steps: 1000,
+            dump_every: 0,
+            dump_file: None,
+            stats_csv: None,
+            bench: false,
+            engine: Engine::Sequential,
+            seed: None,
+            soup_search: None,
+            soup_size: 16,
+            font: None,
+            help: false,
+        }
+    }
+}
+
+impl Options {
+    /// Parses the arguments, not including the program name.
+    ///
+    /// ```
+    /// use life::cli::Options;
+    ///
+    /// let args = vec!["bench", "--steps", "20", "--cells-x", "64"];
+    /// let options = Options::parse(args.into_iter().map(String::from)).unwrap();
+    /// assert!(options.bench);
+    /// assert_eq!(options.steps, 20);
+    /// assert_eq!(options.board_size(), (64, 450));
+    /// assert!(Options::parse(vec!["--steps".to_string()]).is_err());
+    /// ```
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Options, ParseError> {
+        let mut options = Options::default();
+        let mut args = args.into_iter();
+        let mut first = true;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "bench" if first => options.bench = true,
+                "--config" => options.config = Some(Options::value(&arg, args.next())?),
+                "--width" => options.width = Options::value(&arg, args.next())?,
+                "--height" => options.height = Options::value(&arg, args.next())?,
+                "--cells-x" => options.cells_x = Some(Options::value(&arg, args.next())?),
+                "--cells-y" => options.cells_y = Some(Options::value(&arg, args.next())?),
+                "--pattern" => options.pattern = Some(Options::value(&arg, args.next())?),
+                "--stdin" => options.pattern = Some(PathBuf::from("-")),
+                "--image" => options.image = Some(Options::value(&arg, args.next())?),
+                "--threshold" => options.threshold = Options::value(&arg, args.next())?,
+                "--rule" => options.rule = Some(Options::value(&arg, args.next())?),
+                "--boundary" => options.boundary = Some(Options::value(&arg, args.next())?),
+                "--noise" => options.noise = Some(Options::value(&arg, args.next())?),
+                "--fg-color" => options.fg_color = Some(Options::color(&arg, args.next())?),
+                "--bg-color" => options.bg_color = Some(Options::color(&arg, args.next())?),
+                "--grid-color" => options.grid_color = Some(Options::color(&arg, args.next())?),
+                "--young-color" => options.young_color = Some(Options::color(&arg, args.next())?),
+                "--old-color" => options.old_color = Some(Options::color(&arg, args.next())?),
+                "--max-age" => options.max_age = Some(Options::value(&arg, args.next())?),
+                "--renderer" => options.renderer = Options::value(&arg, args.next())?,
+                "--ups" => options.ups = Some(Options::value(&arg, args.next())?),
+                "--fade" => options.fade_frames = Some(Options::value(&arg, args.next())?),
+                "--record-scale" => match Options::value(&arg, args.next())? {
+                    0 => return Err(ParseError::new("--record-scale must be at least 1")),
+                    scale => options.record_scale = Some(scale),
+                },
+                "--record-delay" => options.record_delay = Some(Options::value(&arg, args.next())?),
+                "--max-fps" => match Options::value(&arg, args.next())? {
+                    0 => return Err(ParseError::new("--max-fps must be at least 1")),
+                    max_fps => options.max_fps = Some(max_fps),
+                },
+                "--headless" => options.headless = true,
+                "--steps" => options.steps = Options::value(&arg, args.next())?,
+                "--dump-every" => options.dump_every = Options::value(&arg, args.next())?,
+                "--dump-file" => options.dump_file = Some(Options::value(&arg, args.next())?),
+                "--stats-csv" => options.stats_csv = Some(Options::value(&arg, args.next())?),
+                "--seed" => options.seed = Some(Options::value(&arg, args.next())?),
+                "--soup-search" => options.soup_search = Some(Options::value(&arg, args.next())?),
+                "--soup-size" => options.soup_size = Options::value(&arg, args.next())?,
+                "--engine" => options.engine = Options::value(&arg, args.next())?,
+                "--font" => options.font = Some(Options::value(&arg, args.next())?),
+                "--help" | "-h" => options.help = true,
+                _ => return Err(ParseError::new(format!("unknown option '{}'", arg))),
+            }
+            first = false;
+        }
+        Ok(options)
+    }
+
+    // (width, height) of the board in cells
+    pub fn board_size(&self) -> (u32, u32) {
+        (
+            self.cells_x.unwrap_or(self.width as u32 / 2),
+            self.cells_y.unwrap_or(self.height as u32 / 2),
+        )
+    }
+
+    // the value following a flag like `--steps`
+    fn value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, ParseError> {
+        let value = value.ok_or_else(|| ParseError::new(format!("{} needs a value", flag)))?;
+        value
+            .parse()
+            .map_err(|_| ParseError::new(format!("invalid value '{}' for {}", value, flag)))
+    }
+
+    // like `value`, for the hex colors `Color` has no `FromStr` for
+    fn color(flag: &str, value: Option<String>) -> Result<Color, ParseError> {
+        let value = value.ok_or_else(|| ParseError::new(format!("{} needs a value", flag)))?;
+        parse_hex(&value)
+    }
+}
@@ -0,0 +1,64 @@
+use crate::error::ParseError;
+
+// An RGBA color with each channel from 0.0 to 1.0, the way piston's
+// graphics takes them.
+pub type Color = [f32; 4];
+
+/// Parses a `#rgb` or `#rrggbb` hex color into an opaque `Color`.
+///
+/// ```
+/// use life::color::parse_hex;
+///
+/// assert_eq!(parse_hex("#fff").unwrap(), [1.0, 1.0, 1.0, 1.0]);
+/// assert_eq!(parse_hex("#ff0000").unwrap(), [1.0, 0.0, 0.0, 1.0]);
+/// assert_eq!(parse_hex("#202020").unwrap()[0], 32.0 / 255.0);
+/// assert!(parse_hex("#12345").is_err());
+/// assert!(parse_hex("zzzzzz").is_err());
+/// ```
+pub fn parse_hex(text: &str) -> Result<Color, ParseError> {
+    let invalid = || ParseError::new(format!("invalid color '{}'", text));
+    let digits = text.strip_prefix('#').ok_or_else(invalid)?;
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+
+    let channel = |hex: &str| u8::from_str_radix(hex, 16).map(|value| value as f32 / 255.0);
+    let (red, green, blue) = match digits.len() {
+        // each digit doubled, so #f80 is #ff8800
+        3 => (
+            channel(&digits[0..1].repeat(2)),
+            channel(&digits[1..2].repeat(2)),
+            channel(&digits[2..3].repeat(2)),
+        ),
+        6 => (
+            channel(&digits[0..2]),
+            channel(&digits[2..4]),
+            channel(&digits[4..6]),
+        ),
+        _ => return Err(invalid()),
+    };
+    match (red, green, blue) {
+        (Ok(red), Ok(green), Ok(blue)) => Ok([red, green, blue, 1.0]),
+        _ => Err(invalid()),
+    }
+}
+
+/// The color a fraction `t` of the way from `from` to `to`, with `t` clamped
+/// to 0.0..=1.0.
+///
+/// ```
+/// use life::color::lerp;
+///
+/// let blue = [0.0, 0.0, 1.0, 1.0];
+/// let red = [1.0, 0.0, 0.0, 1.0];
+/// assert_eq!(lerp(blue, red, 0.5), [0.5, 0.0, 0.5, 1.0]);
+/// assert_eq!(lerp(blue, red, 2.0), red);
+/// ```
+pub fn lerp(from: Color, to: Color, t: f32) -> Color {
+    let t = t.clamp(0.0, 1.0);
+    let mut color = from;
+    for (channel, &target) in color.iter_mut().zip(to.iter()) {
+        *channel += (target - *channel) * t;
+    }
+    color
+}
@@ -0,0 +1,156 @@
+use crate::cli::Options;
+use crate::color::parse_hex;
+use crate::error::ParseError;
+use crate::game_of_life::Boundary;
+use crate::rule::Rule;
+use serde::Deserialize;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Where settings come from when `--config` isn't given, if it exists.
+pub const DEFAULT_PATH: &str = "life.toml";
+
+// Defaults read from a TOML file, so they don't all have to go on the
+// command line. Everything is optional, and anything given as a flag wins.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    // board size in cells
+    pub cells_x: Option<u32>,
+    pub cells_y: Option<u32>,
+    // in B/S notation, like "B36/S23"
+    pub rule: Option<Rule>,
+    // "toroidal" or "dead"
+    pub boundary: Option<Boundary>,
+    // chance of each cell flipping every generation
+    pub noise: Option<f64>,
+    // `#rgb` or `#rrggbb`
+    pub fg_color: Option<String>,
+    pub bg_color: Option<String>,
+    pub grid_color: Option<String>,
+    // the age shading's gradient, also `#rgb` or `#rrggbb`, and the age that
+    // reaches `old_color`
+    pub young_color: Option<String>,
+    pub old_color: Option<String>,
+    pub max_age: Option<u32>,
+    pub ups: Option<f64>,
+    pub max_fps: Option<u64>,
+    // frames a dead cell takes to fade out
+    pub fade_frames: Option<u32>,
+    // pixels per cell and hundredths of a second per frame in recordings
+    pub record_scale: Option<u32>,
+    pub record_delay: Option<u16>,
+    pub pattern: Option<PathBuf>,
+}
+
+impl Config {
+    /// Parses the contents of a config file.
+    ///
+    /// ```
+    /// use life::config::Config;
+    /// use life::game_of_life::Boundary;
+    /// use life::rule::Rule;
+    /// use std::path::PathBuf;
+    ///
+    /// let config = Config::from_toml(
+    ///     r##"
+    ///     cells_x = 200
+    ///     cells_y = 120
+    ///     rule = "B36/S23"
+    ///     boundary = "dead"
+    ///     noise = 0.001
+    ///     fg_color = "#ffcc00"
+    ///     bg_color = "#000"
+    ///     grid_color = "#333333"
+    ///     young_color = "#0f0"
+    ///     old_color = "#00f"
+    ///     max_age = 100
+    ///     ups = 60.0
+    ///     max_fps = 30
+    ///     fade_frames = 8
+    ///     record_scale = 3
+    ///     record_delay = 10
+    ///     pattern = "patterns/gosper.rle"
+    ///     "##,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(
+    ///     config,
+    ///     Config {
+    ///         cells_x: Some(200),
+    ///         cells_y: Some(120),
+    ///         rule: Some(Rule::highlife()),
+    ///         boundary: Some(Boundary::Dead),
+    ///         noise: Some(0.001),
+    ///         fg_color: Some("#ffcc00".to_string()),
+    ///         bg_color: Some("#000".to_string()),
+    ///         grid_color: Some("#333333".to_string()),
+    ///         young_color: Some("#0f0".to_string()),
+    ///         old_color: Some("#00f".to_string()),
+    ///         max_age: Some(100),
+    ///         ups: Some(60.0),
+    ///         max_fps: Some(30),
+    ///         fade_frames: Some(8),
+    ///         record_scale: Some(3),
+    ///         record_delay: Some(10),
+    ///         pattern: Some(PathBuf::from("patterns/gosper.rle")),
+    ///     }
+    /// );
+    /// assert_eq!(Config::from_toml("").unwrap(), Config::default());
+    /// assert!(Config::from_toml("speed = 3").is_err());
+    /// ```
+    pub fn from_toml(text: &str) -> Result<Config, ParseError> {
+        toml::from_str(text).map_err(|err| ParseError::new(err.to_string()))
+    }
+
+    pub fn load(path: &Path) -> Result<Config, Box<dyn Error>> {
+        Ok(Config::from_toml(&fs::read_to_string(path)?)?)
+    }
+
+    /// Fills in whatever the command line left unset.
+    ///
+    /// ```
+    /// use life::cli::Options;
+    /// use life::config::Config;
+    ///
+    /// let config = Config::from_toml("cells_x = 200\ncells_y = 120\nups = 60.0").unwrap();
+    /// let args = vec!["--cells-x", "64"];
+    /// let mut options = Options::parse(args.into_iter().map(String::from)).unwrap();
+    /// config.apply(&mut options).unwrap();
+    /// assert_eq!(options.board_size(), (64, 120));
+    /// assert_eq!(options.ups, Some(60.0));
+    /// ```
+    pub fn apply(&self, options: &mut Options) -> Result<(), ParseError> {
+        options.cells_x = options.cells_x.or(self.cells_x);
+        options.cells_y = options.cells_y.or(self.cells_y);
+        options.rule = options.rule.or(self.rule);
+        options.boundary = options.boundary.or(self.boundary);
+        options.noise = options.noise.or(self.noise);
+        if options.fg_color.is_none() {
+            options.fg_color = self.fg_color.as_deref().map(parse_hex).transpose()?;
+        }
+        if options.bg_color.is_none() {
+            options.bg_color = self.bg_color.as_deref().map(parse_hex).transpose()?;
+        }
+        if options.grid_color.is_none() {
+            options.grid_color = self.grid_color.as_deref().map(parse_hex).transpose()?;
+        }
+        if options.young_color.is_none() {
+            options.young_color = self.young_color.as_deref().map(parse_hex).transpose()?;
+        }
+        if options.old_color.is_none() {
+            options.old_color = self.old_color.as_deref().map(parse_hex).transpose()?;
+        }
+        options.max_age = options.max_age.or(self.max_age);
+        options.ups = options.ups.or(self.ups);
+        options.max_fps = options.max_fps.or(self.max_fps);
+        options.fade_frames = options.fade_frames.or(self.fade_frames);
+        options.record_scale = options.record_scale.or(self.record_scale);
+        options.record_delay = options.record_delay.or(self.record_delay);
+        if options.pattern.is_none() {
+            options.pattern = self.pattern.clone();
+        }
+        Ok(())
+    }
+}
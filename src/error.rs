@@ -0,0 +1,24 @@
+use std::error::Error;
+use std::fmt;
+
+// Returned when a rule string or pattern file can't be understood.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    message: String,
+}
+
+impl ParseError {
+    pub fn new<S: Into<String>>(message: S) -> ParseError {
+        ParseError {
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "parse error: {}", self.message)
+    }
+}
+
+impl Error for ParseError {}
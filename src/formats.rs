@@ -0,0 +1,589 @@
+use crate::error::ParseError;
+use crate::game_of_life::{Cell, Universe};
+use crate::rule::Rule;
+use std::path::Path;
+
+// The pattern file formats `Universe` can read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Rle,
+    Life106,
+    Plaintext,
+}
+
+impl Format {
+    /// The format a file's extension says it's in.
+    ///
+    /// ```
+    /// use life::formats::Format;
+    ///
+    /// assert_eq!(Format::from_path("glider.rle".as_ref()), Some(Format::Rle));
+    /// assert_eq!(Format::from_path("glider.LIF".as_ref()), Some(Format::Life106));
+    /// assert_eq!(Format::from_path("glider.cells".as_ref()), Some(Format::Plaintext));
+    /// assert_eq!(Format::from_path("glider.png".as_ref()), None);
+    /// ```
+    pub fn from_path(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "rle" => Some(Format::Rle),
+            "lif" | "life" => Some(Format::Life106),
+            "cells" | "txt" => Some(Format::Plaintext),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format from the text itself, for when there's no file
+    /// name to go by.
+    ///
+    /// ```
+    /// use life::formats::Format;
+    ///
+    /// assert_eq!(Format::sniff("#N Glider\nx = 3, y = 3\nbob$2bo$3o!"), Format::Rle);
+    /// assert_eq!(Format::sniff("#Life 1.06\n0 -1\n1 0"), Format::Life106);
+    /// assert_eq!(Format::sniff("!Name: Glider\n.O.\n..O\nOOO"), Format::Plaintext);
+    /// ```
+    pub fn sniff(text: &str) -> Format {
+        let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+        for line in &mut lines {
+            if line.starts_with("#Life 1.06") {
+                return Format::Life106;
+            }
+            if line.starts_with('#') || line.starts_with('!') {
+                continue;
+            }
+            if line.starts_with('x') && line.contains('=') {
+                return Format::Rle;
+            }
+            if line.chars().all(|c| c == '.' || c == 'O' || c == '*') {
+                return Format::Plaintext;
+            }
+            if line
+                .split_whitespace()
+                .all(|part| part.parse::<i64>().is_ok())
+            {
+                return Format::Life106;
+            }
+            break;
+        }
+        Format::Rle
+    }
+}
+
+impl Universe {
+    // Parses a pattern in any of the formats onto a board at least `width` x
+    // `height`, centered if the pattern is smaller than that.
+    pub fn from_pattern(
+        text: &str,
+        format: Format,
+        width: u32,
+        height: u32,
+    ) -> Result<Universe, ParseError> {
+        let mut universe = match format {
+            Format::Rle => Universe::from_rle(text)?,
+            Format::Life106 => return Universe::from_life106(text, width, height),
+            Format::Plaintext => Universe::from_plaintext(text)?,
+        };
+        if universe.width() < width || universe.height() < height {
+            universe.resize(universe.width().max(width), universe.height().max(height));
+            universe.center();
+        }
+        Ok(universe)
+    }
+
+    // Parses the run length encoded format used by LifeWiki and Golly. The
+    // board is sized by the `x = .., y = ..` header; a `rule =` header entry
+    // or a `#r` line sets the rule.
+    pub fn from_rle(text: &str) -> Result<Universe, ParseError> {
+        let mut size: Option<(u32, u32)> = None;
+        let mut rule: Option<Rule> = None;
+        let mut cells: Vec<Cell> = Vec::new();
+        let mut row: u32 = 0;
+        let mut col: u32 = 0;
+        let mut run: Option<u32> = None;
+        let mut finished = false;
+
+        for line in text.lines() {
+            let line = line.trim();
+            if finished {
+                break;
+            }
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(comment) = line.strip_prefix('#') {
+                if let Some(r) = comment.strip_prefix('r') {
+                    rule = Some(r.parse()?);
+                }
+                continue;
+            }
+
+            let (width, height) = match size {
+                Some(size) => size,
+                None => {
+                    let (width, height, header_rule) = parse_rle_header(line)?;
+                    if header_rule.is_some() {
+                        rule = header_rule;
+                    }
+                    let len = width.checked_mul(height).ok_or_else(|| {
+                        ParseError::new(format!("invalid RLE size {}x{}", width, height))
+                    })?;
+                    size = Some((width, height));
+                    cells = vec![Cell::Dead; len as usize];
+                    continue;
+                }
+            };
+
+            for c in line.chars() {
+                match c {
+                    '0'..='9' => {
+                        let digit = c.to_digit(10).unwrap();
+                        run = run
+                            .unwrap_or(0)
+                            .checked_mul(10)
+                            .and_then(|run| run.checked_add(digit));
+                        if run.is_none() {
+                            return Err(ParseError::new("RLE run count is too big"));
+                        }
+                        continue;
+                    }
+                    'b' | 'o' => {
+                        let count = run.unwrap_or(1);
+                        // col never passes width, so this can't overflow
+                        if count > width - col || row >= height {
+                            return Err(ParseError::new("RLE pattern is larger than its header"));
+                        }
+                        if c == 'o' {
+                            for i in col..col + count {
+                                cells[(row * width + i) as usize] = Cell::Alive;
+                            }
+                        }
+                        col += count;
+                    }
+                    '$' => {
+                        row = row.saturating_add(run.unwrap_or(1));
+                        col = 0;
+                    }
+                    '!' => {
+                        finished = true;
+                        break;
+                    }
+                    c if c.is_whitespace() => {}
+                    c => return Err(ParseError::new(format!("unexpected '{}' in RLE data", c))),
+                }
+                run = None;
+            }
+        }
+
+        let (width, height) = size.ok_or_else(|| ParseError::new("missing RLE header"))?;
+        let mut universe = Universe::with_cells(width, height, cells);
+        if let Some(rule) = rule {
+            universe.set_rule(rule);
+        }
+        Ok(universe)
+    }
+
+    // Parses a Life 1.06 file: `#Life 1.06` followed by one `x y` pair per
+    // live cell. Coordinates may be negative, so the pattern is centered on a
+    // `width` x `height` board.
+    pub fn from_life106(text: &str, width: u32, height: u32) -> Result<Universe, ParseError> {
+        let mut coords: Vec<(i64, i64)> = Vec::new();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.split_whitespace().map(|part| part.parse::<i64>());
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(Ok(x)), Some(Ok(y)), None) => coords.push((x, y)),
+                _ => {
+                    return Err(ParseError::new(format!(
+                        "invalid Life 1.06 line '{}'",
+                        line
+                    )))
+                }
+            }
+        }
+
+        let len = width
+            .checked_mul(height)
+            .ok_or_else(|| ParseError::new(format!("{}x{} board is too big", width, height)))?;
+        let mut cells = vec![Cell::Dead; len as usize];
+        if !coords.is_empty() {
+            let min_x = coords.iter().map(|&(x, _)| x).min().unwrap();
+            let max_x = coords.iter().map(|&(x, _)| x).max().unwrap();
+            let min_y = coords.iter().map(|&(_, y)| y).min().unwrap();
+            let max_y = coords.iter().map(|&(_, y)| y).max().unwrap();
+            let pattern_width = max_x - min_x + 1;
+            let pattern_height = max_y - min_y + 1;
+            if pattern_width > width as i64 || pattern_height > height as i64 {
+                return Err(ParseError::new(format!(
+                    "{}x{} pattern doesn't fit a {}x{} board",
+                    pattern_width, pattern_height, width, height
+                )));
+            }
+
+            let offset_x = (width as i64 - pattern_width) / 2 - min_x;
+            let offset_y = (height as i64 - pattern_height) / 2 - min_y;
+            for (x, y) in coords {
+                let col = (x + offset_x) as u32;
+                let row = (y + offset_y) as u32;
+                cells[(row * width + col) as usize] = Cell::Alive;
+            }
+        }
+        Ok(Universe::with_cells(width, height, cells))
+    }
+
+    // Parses the plaintext `.cells` format: `!` comment lines, then one line
+    // per row with `.` for dead and `O` for alive, or `*` as in Life 1.05
+    // style grids. Short rows are padded out with dead cells to the longest
+    // row.
+    pub fn from_plaintext(text: &str) -> Result<Universe, ParseError> {
+        let mut rows: Vec<&str> = text
+            .lines()
+            .map(|line| line.trim_end())
+            .filter(|line| !line.starts_with('!'))
+            .collect();
+        while rows.last() == Some(&"") {
+            rows.pop();
+        }
+
+        let width = rows
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(0) as u32;
+        let height = rows.len() as u32;
+        let mut cells = vec![Cell::Dead; (width * height) as usize];
+        for (row, line) in rows.iter().enumerate() {
+            for (col, c) in line.chars().enumerate() {
+                match c {
+                    '.' => {}
+                    'O' | '*' => cells[row * width as usize + col] = Cell::Alive,
+                    c => {
+                        return Err(ParseError::new(format!(
+                            "unexpected '{}' in plaintext pattern",
+                            c
+                        )))
+                    }
+                }
+            }
+        }
+        Ok(Universe::with_cells(width, height, cells))
+    }
+
+    /// The whole board in the plaintext format, one line per row with `.`
+    /// for dead and `O` for alive; `from_plaintext` reads it back.
+    ///
+    /// ```
+    /// use life::game_of_life::Universe;
+    ///
+    /// let glider = ".O...\n..O..\nOOO..\n.....\n";
+    /// let universe = Universe::from_plaintext(glider).unwrap();
+    /// assert_eq!(universe.to_plaintext(), glider);
+    /// assert!(Universe::from_plaintext(&universe.to_plaintext()).unwrap() == universe);
+    /// ```
+    pub fn to_plaintext(&self) -> String {
+        let mut out = String::with_capacity(((self.width() + 1) * self.height()) as usize);
+        for row in 0..self.height() {
+            for col in 0..self.width() {
+                out.push(if self.get_cell(row, col) == Cell::Alive {
+                    'O'
+                } else {
+                    '.'
+                });
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// The whole board for a terminal, `█` for alive and a space for
+    /// anything else, with rows separated by newlines.
+    ///
+    /// ```
+    /// use life::game_of_life::Universe;
+    ///
+    /// let universe = Universe::from_plaintext(".O.\n..O\nOOO").unwrap();
+    /// assert_eq!(universe.to_ascii_frame(), " █ \n  █\n███");
+    /// ```
+    pub fn to_ascii_frame(&self) -> String {
+        let rows: Vec<String> = (0..self.height())
+            .map(|row| {
+                (0..self.width())
+                    .map(|col| {
+                        if self.get_cell(row, col) == Cell::Alive {
+                            '█'
+                        } else {
+                            ' '
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        rows.join("\n")
+    }
+
+    // Writes the live cells, cropped to their bounding box, as RLE. The rule
+    // is only written out when it isn't Conway's.
+    pub fn to_rle(&self) -> String {
+        let (min_row, min_col, width, height) = match self.bounding_box() {
+            Some((min_row, min_col, max_row, max_col)) => (
+                min_row,
+                min_col,
+                max_col - min_col + 1,
+                max_row - min_row + 1,
+            ),
+            None => (0, 0, 0, 0),
+        };
+        let mut grid = vec![false; (width * height) as usize];
+        for (col, row) in self.live_cells() {
+            grid[((row - min_row) * width + col - min_col) as usize] = true;
+        }
+
+        let mut out = format!("x = {}, y = {}", width, height);
+        if self.rule() != Rule::conway() {
+            out.push_str(&format!(", rule = {}", self.rule()));
+        }
+        out.push('\n');
+
+        let mut writer = RleWriter::default();
+        for row in 0..height {
+            let cells = &grid[(row * width) as usize..((row + 1) * width) as usize];
+            let mut col = 0;
+            while col < width {
+                let alive = cells[col as usize];
+                let start = col;
+                while col < width && cells[col as usize] == alive {
+                    col += 1;
+                }
+                // dead cells at the end of a row are implied by the `$`
+                if alive || col < width {
+                    writer.push(col - start, if alive { 'o' } else { 'b' });
+                }
+            }
+            if row + 1 < height {
+                writer.push(1, '$');
+            }
+        }
+        writer.push(1, '!');
+        out.push_str(&writer.finish());
+        out
+    }
+}
+
+// Collects RLE tokens, merging repeated `$`s into one run and wrapping lines
+// at the customary 70 characters.
+#[derive(Default)]
+struct RleWriter {
+    out: String,
+    line_len: usize,
+    pending: Option<(u32, char)>,
+}
+
+impl RleWriter {
+    fn push(&mut self, count: u32, tag: char) {
+        match self.pending {
+            Some((pending, pending_tag)) if pending_tag == tag => {
+                self.pending = Some((pending + count, tag));
+            }
+            _ => {
+                self.flush();
+                self.pending = Some((count, tag));
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Some((count, tag)) = self.pending.take() {
+            let token = if count == 1 {
+                tag.to_string()
+            } else {
+                format!("{}{}", count, tag)
+            };
+            if self.line_len + token.len() > 70 {
+                self.out.push('\n');
+                self.line_len = 0;
+            }
+            self.line_len += token.len();
+            self.out.push_str(&token);
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.flush();
+        self.out.push('\n');
+        self.out
+    }
+}
+
+// "x = 3, y = 3, rule = B3/S23" -> (3, 3, Some(B3/S23))
+fn parse_rle_header(line: &str) -> Result<(u32, u32, Option<Rule>), ParseError> {
+    let mut width = None;
+    let mut height = None;
+    let mut rule = None;
+    for field in line.split(',') {
+        let mut parts = field.splitn(2, '=');
+        let key = parts.next().unwrap_or("").trim();
+        let value = parts
+            .next()
+            .ok_or_else(|| ParseError::new(format!("invalid RLE header '{}'", line)))?
+            .trim();
+        match key {
+            "x" => width = value.parse().ok(),
+            "y" => height = value.parse().ok(),
+            "rule" => rule = Some(value.parse()?),
+            _ => {}
+        }
+    }
+    match (width, height) {
+        (Some(width), Some(height)) => Ok((width, height, rule)),
+        _ => Err(ParseError::new(format!("invalid RLE header '{}'", line))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_glider() {
+        let glider =
+            Universe::from_rle("#N Glider\nx = 3, y = 3, rule = B3/S23\nbob$2bo$3o!").unwrap();
+        assert_eq!((glider.width(), glider.height()), (3, 3));
+        assert_eq!(glider.to_plaintext(), ".O.\n..O\nOOO\n");
+        assert_eq!(glider.rule(), Rule::conway());
+    }
+
+    #[test]
+    fn parses_a_blinker() {
+        let blinker = Universe::from_rle("x = 3, y = 1\n3o!").unwrap();
+        assert_eq!(blinker.to_plaintext(), "OOO\n");
+    }
+
+    #[test]
+    fn runs_carry_across_lines() {
+        // the data is split mid-row, and even between a count and its tag
+        let universe = Universe::from_rle("x = 6, y = 3\n2o2\nbo$\n6o$o4b\no!").unwrap();
+        assert_eq!(universe.to_plaintext(), "OO..O.\nOOOOOO\nO....O\n");
+    }
+
+    #[test]
+    fn blank_rows_come_from_runs_of_dollars() {
+        let universe = Universe::from_rle("x = 1, y = 4\no3$o!").unwrap();
+        assert_eq!(universe.to_plaintext(), "O\n.\n.\nO\n");
+    }
+
+    #[test]
+    fn rule_lines_set_the_rule() {
+        let universe = Universe::from_rle("#r B36/S23\nx = 1, y = 1\no!").unwrap();
+        assert_eq!(universe.rule(), Rule::highlife());
+        let universe = Universe::from_rle("x = 1, y = 1, rule = B2/S\no!").unwrap();
+        assert_eq!(universe.rule(), Rule::seeds());
+        assert!(Universe::from_rle("#r B9/S\nx = 1, y = 1\no!").is_err());
+    }
+
+    #[test]
+    fn rejects_bad_sizes() {
+        assert!(Universe::from_rle("x = 70000, y = 70000\no!").is_err());
+        assert!(Universe::from_rle("x = 2, y = 1\n3o!").is_err());
+        assert!(Universe::from_rle("x = 2, y = 1\no$o!").is_err());
+        assert!(Universe::from_rle("bo$2bo$3o!").is_err());
+    }
+
+    #[test]
+    fn rejects_run_counts_that_overflow() {
+        assert!(Universe::from_rle("x = 3, y = 3\n99999999999o!").is_err());
+        assert!(Universe::from_rle("x = 3, y = 3\n4294967295bo!").is_err());
+        assert!(Universe::from_rle("x = 3, y = 3\n4294967295$o!").is_err());
+    }
+
+    #[test]
+    fn empty_board_round_trips() {
+        let empty = Universe::from_plaintext("...\n...").unwrap();
+        assert_eq!(empty.to_rle(), "x = 0, y = 0\n!\n");
+        let parsed = Universe::from_rle(&empty.to_rle()).unwrap();
+        assert_eq!(parsed.population(), 0);
+        assert_eq!(parsed.to_rle(), empty.to_rle());
+    }
+
+    // live (row, col) cells relative to the top-left of their bounding box
+    fn normalized(universe: &Universe) -> Vec<(u32, u32)> {
+        let (min_row, min_col, _, _) = universe.bounding_box().unwrap_or((0, 0, 0, 0));
+        let mut cells: Vec<(u32, u32)> = universe
+            .live_cells()
+            .map(|(col, row)| (row - min_row, col - min_col))
+            .collect();
+        cells.sort_unstable();
+        cells
+    }
+
+    #[test]
+    fn rle_round_trips_with_its_rule() {
+        // wide enough that the encoded rows have to wrap
+        let mut universe = Universe::new_random(120, 40, 0.3, 11);
+        universe.set_rule(Rule::highlife());
+        let rle = universe.to_rle();
+        assert!(rle.starts_with("x = "));
+        assert!(rle.lines().next().unwrap().ends_with(", rule = B36/S23"));
+        assert!(rle.lines().all(|line| line.len() <= 70));
+
+        let copy = Universe::from_rle(&rle).unwrap();
+        assert_eq!(copy.rule(), Rule::highlife());
+        assert_eq!(normalized(&copy), normalized(&universe));
+    }
+
+    #[test]
+    fn rle_crops_to_the_live_cells() {
+        let mut universe = Universe::from_plaintext(".....\n..O..\n...O.\n.OOO.\n.....").unwrap();
+        assert_eq!(universe.to_rle(), "x = 3, y = 3\nbo$2bo$3o!\n");
+        universe.set_rule(Rule::conway());
+        assert!(!universe.to_rle().contains("rule"));
+    }
+
+    #[test]
+    fn life106_places_exactly_its_cells() {
+        let universe = Universe::from_life106("#Life 1.06\n-1 -1\n0 0\n1 -1\n", 5, 4).unwrap();
+        // a 3x2 pattern centered on the board, x = -1 landing in column 1
+        assert_eq!(universe.to_plaintext(), ".....\n.O.O.\n..O..\n.....\n");
+        assert_eq!(universe.population(), 3);
+    }
+
+    #[test]
+    fn life106_rejects_bad_input() {
+        assert!(Universe::from_life106("#Life 1.06\n0 0\n9 0\n", 5, 5).is_err());
+        assert!(Universe::from_life106("#Life 1.06\n0\n", 5, 5).is_err());
+        assert!(Universe::from_life106("#Life 1.06\n0 0 0\n", 5, 5).is_err());
+        assert!(Universe::from_life106("#Life 1.06\n0 0\n", 70_000, 70_000).is_err());
+    }
+
+    #[test]
+    fn parses_a_plaintext_block() {
+        let block = Universe::from_plaintext("!Name: Block\n!\n...\n.OO\n.OO\n").unwrap();
+        assert_eq!((block.width(), block.height()), (3, 3));
+        let mut cells: Vec<(u32, u32)> = block.live_cells().collect();
+        cells.sort_unstable();
+        assert_eq!(cells, vec![(1, 1), (1, 2), (2, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn plaintext_pads_short_rows() {
+        let universe = Universe::from_plaintext("O\n.O.O\n\n..O").unwrap();
+        assert_eq!((universe.width(), universe.height()), (4, 4));
+        assert_eq!(universe.to_plaintext(), "O...\n.O.O\n....\n..O.\n");
+        assert!(Universe::from_plaintext(".O.\nOxO").is_err());
+    }
+
+    #[test]
+    fn sniffed_text_parses_in_its_format() {
+        let texts = [
+            "#N Glider\nx = 3, y = 3\nbob$2bo$3o!",
+            "#Life 1.06\n1 0\n2 1\n0 2\n1 2\n2 2",
+            "!Name: Glider\n.O.\n..O\nOOO",
+            ".*.\n..*\n***",
+        ];
+        for text in texts.iter() {
+            let universe = Universe::from_pattern(text, Format::sniff(text), 3, 3).unwrap();
+            assert_eq!(universe.to_plaintext(), ".O.\n..O\nOOO\n", "{}", text);
+        }
+    }
+}
@@ -1,15 +1,114 @@
-#[repr(u8)]
+use crate::error::ParseError;
+use crate::rng::XorShift;
+use crate::rule::Rule;
+use rayon::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Cell {
-    Dead = 0,
-    Alive = 1,
+    Dead,
+    Alive,
+    // a cell on its way out under a Generations rule; counts up from 2 and
+    // neither counts as a neighbor nor can be reborn until it's dead
+    Dying(u8),
+}
+
+impl Cell {
+    // 0 for dead, 1 for alive and 2.. for the dying states
+    pub fn state(self) -> u8 {
+        match self {
+            Cell::Dead => 0,
+            Cell::Alive => 1,
+            Cell::Dying(state) => state,
+        }
+    }
+}
+
+// What lies beyond the edges of the board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Boundary {
+    // edges wrap around, so the board is a torus
+    #[cfg_attr(feature = "serde", serde(alias = "toroidal"))]
+    Toroidal,
+    // everything off the board is permanently dead
+    #[cfg_attr(feature = "serde", serde(alias = "dead"))]
+    Dead,
 }
 
+impl std::str::FromStr for Boundary {
+    type Err = ParseError;
+
+    fn from_str(name: &str) -> Result<Boundary, ParseError> {
+        match name {
+            "toroidal" => Ok(Boundary::Toroidal),
+            "dead" => Ok(Boundary::Dead),
+            _ => Err(ParseError::new(format!("unknown boundary '{}'", name))),
+        }
+    }
+}
+
+// Which surrounding cells count as neighbors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Neighborhood {
+    // all eight surrounding cells
+    Moore,
+    // only the four orthogonally adjacent cells
+    VonNeumann,
+}
+
+// The board as it was at generation 0, for `reset`.
+#[derive(Clone)]
+struct Seed {
+    width: u32,
+    height: u32,
+    cells: Vec<Cell>,
+    colors: Vec<u8>,
+}
+
+// Serialized through `Snapshot`, so only the board and its settings are
+// saved and the caches are rebuilt on load.
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(into = "Snapshot", try_from = "Snapshot")
+)]
 pub struct Universe {
-    pub width: u32,
-    pub height: u32,
+    width: u32,
+    height: u32,
     cells: Vec<Cell>,
-    pub live_cells: Vec<(u32, u32)>,
+    next_cells: Vec<Cell>,
+    // Immigration style colors of live cells, 0 for everything else
+    colors: Vec<u8>,
+    // set once any cell has been given a color; until then every color is
+    // 0 and births can skip working out their parents' majority
+    colored: bool,
+    // generations each live cell has survived since it was born
+    ages: Vec<u32>,
+    live_cells: Vec<(u32, u32)>,
+    changed_cells: Vec<(u32, u32)>,
+    stable: bool,
+    last_births: usize,
+    last_deaths: usize,
+    generation: u64,
+    rule: Rule,
+    boundary: Boundary,
+    neighborhood: Neighborhood,
+    history: VecDeque<Vec<Cell>>,
+    history_depth: usize,
+    // saved on the first step out of generation 0, so it includes any
+    // edits made before the run started
+    seed: Option<Seed>,
+    // chance of each cell flipping after every update, and what decides it
+    noise: f64,
+    noise_rng: XorShift,
 }
 
 impl Universe {
@@ -17,66 +116,945 @@ impl Universe {
         (row * self.width + column) as usize
     }
 
+    // the cell at (row + delta_row, column + delta_col), or None when that
+    // falls off the edge of a board with dead boundaries
+    fn neighbor(&self, row: u32, column: u32, delta_row: i64, delta_col: i64) -> Option<usize> {
+        let height = self.height as i64;
+        let width = self.width as i64;
+        let mut neighbor_row = row as i64 + delta_row;
+        let mut neighbor_col = column as i64 + delta_col;
+        match self.boundary {
+            Boundary::Toroidal => {
+                neighbor_row = neighbor_row.rem_euclid(height);
+                neighbor_col = neighbor_col.rem_euclid(width);
+            }
+            Boundary::Dead => {
+                if neighbor_row < 0
+                    || neighbor_row >= height
+                    || neighbor_col < 0
+                    || neighbor_col >= width
+                {
+                    return None;
+                }
+            }
+        }
+        Some(self.get_index(neighbor_row as u32, neighbor_col as u32))
+    }
+
+    // indices of the cells in the neighborhood of (row, column)
+    fn neighbors(&self, row: u32, column: u32) -> impl Iterator<Item = usize> + '_ {
+        const MOORE: [(i64, i64); 8] = [
+            (-1, -1),
+            (-1, 0),
+            (-1, 1),
+            (0, -1),
+            (0, 1),
+            (1, -1),
+            (1, 0),
+            (1, 1),
+        ];
+        const VON_NEUMANN: [(i64, i64); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+
+        let deltas: &[(i64, i64)] = match self.neighborhood {
+            Neighborhood::Moore => &MOORE,
+            Neighborhood::VonNeumann => &VON_NEUMANN,
+        };
+        deltas.iter().filter_map(move |&(delta_row, delta_col)| {
+            self.neighbor(row, column, delta_row, delta_col)
+        })
+    }
+
     fn live_neighbor_count(&self, row: u32, column: u32) -> u8 {
-        let mut count = 0;
-        for delta_row in [self.height - 1, 0, 1].iter().cloned() {
-            for delta_col in [self.width - 1, 0, 1].iter().cloned() {
-                if delta_row == 0 && delta_col == 0 {
-                    continue;
+        self.neighbors(row, column)
+            .filter(|&idx| self.cells[idx] == Cell::Alive)
+            .count() as u8
+    }
+
+    // the color most common among the live neighbors of (row, column), the
+    // lowest one winning a tie
+    fn majority_color(&self, row: u32, column: u32) -> u8 {
+        let mut tally = [(0u8, 0u8); 8];
+        let mut used = 0;
+        for idx in self.neighbors(row, column) {
+            if self.cells[idx] != Cell::Alive {
+                continue;
+            }
+            let color = self.colors[idx];
+            match tally[..used].iter_mut().find(|(c, _)| *c == color) {
+                Some((_, count)) => *count += 1,
+                None => {
+                    tally[used] = (color, 1);
+                    used += 1;
                 }
+            }
+        }
+        tally[..used]
+            .iter()
+            .max_by_key(|&&(color, count)| (count, std::cmp::Reverse(color)))
+            .map_or(0, |&(color, _)| color)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
 
-                let neighbor_row = (row + delta_row) % self.height;
-                let neighbor_col = (column + delta_col) % self.width;
-                let idx = self.get_index(neighbor_row, neighbor_col);
-                count += self.cells[idx] as u8;
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    // (min_row, min_col, max_row, max_col) of the live cells, inclusive
+    pub fn bounding_box(&self) -> Option<(u32, u32, u32, u32)> {
+        let mut cells = self.live_cells.iter();
+        let &(col, row) = cells.next()?;
+        let mut bounds = (row, col, row, col);
+        for &(col, row) in cells {
+            bounds.0 = bounds.0.min(row);
+            bounds.1 = bounds.1.min(col);
+            bounds.2 = bounds.2.max(row);
+            bounds.3 = bounds.3.max(col);
+        }
+        Some(bounds)
+    }
+
+    // live cells as (col, row), cached and rebuilt on every update
+    pub fn live_cells(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.live_cells.iter().cloned()
+    }
+
+    // number of live cells, kept in step with the live cell cache
+    pub fn population(&self) -> usize {
+        self.live_cells.len()
+    }
+
+    /// Fraction of the board that's alive, from 0.0 to 1.0.
+    ///
+    /// ```
+    /// use life::game_of_life::Universe;
+    ///
+    /// let mut universe = Universe::new(8, 8);
+    /// universe.clear();
+    /// assert_eq!(universe.density(), 0.0);
+    /// ```
+    pub fn density(&self) -> f64 {
+        let area = self.width as usize * self.height as usize;
+        if area == 0 {
+            0.0
+        } else {
+            self.population() as f64 / area as f64
+        }
+    }
+
+    /// Shannon entropy, in bits, of the 2x2 blocks tiling the board: 0.0 when
+    /// every block looks the same, up to 4.0 when all sixteen block patterns
+    /// are equally common. A trailing odd row or column is ignored.
+    ///
+    /// ```
+    /// use life::game_of_life::Universe;
+    ///
+    /// // a checkerboard is half alive but perfectly regular
+    /// let checkerboard = Universe::from_plaintext("O.O.\n.O.O\nO.O.\n.O.O").unwrap();
+    /// assert_eq!(checkerboard.density(), 0.5);
+    /// assert_eq!(checkerboard.entropy(), 0.0);
+    /// ```
+    pub fn entropy(&self) -> f64 {
+        let mut counts = [0usize; 16];
+        for row in (0..self.height / 2 * 2).step_by(2) {
+            for col in (0..self.width / 2 * 2).step_by(2) {
+                let mut pattern = 0;
+                for (bit, &(dr, dc)) in [(0, 0), (0, 1), (1, 0), (1, 1)].iter().enumerate() {
+                    if self.cells[self.get_index(row + dr, col + dc)] == Cell::Alive {
+                        pattern |= 1 << bit;
+                    }
+                }
+                counts[pattern] += 1;
             }
         }
-        count
+
+        let blocks: usize = counts.iter().sum();
+        counts
+            .iter()
+            .filter(|&&count| count > 0)
+            .map(|&count| {
+                let p = count as f64 / blocks as f64;
+                p * (1.0 / p).log2()
+            })
+            .sum()
     }
 
-    pub fn update(&mut self) {
-        let mut next = self.cells.clone();
+    // number of times update has been called since creation or the last reset
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
 
-        self.live_cells.clear();
+    pub fn reset_generation(&mut self) {
+        self.generation = 0;
+    }
+
+    // puts the board back the way it was at generation 0, keeping the
+    // current rule and other settings
+    pub fn reset(&mut self) {
+        let seed = match self.seed.take() {
+            Some(seed) => seed,
+            // never stepped, so it's already there
+            None => return,
+        };
+
+        self.history.clear();
+        self.generation = 0;
+        self.stable = false;
+        self.ages = vec![0; seed.cells.len()];
+        self.colors = seed.colors;
+        if seed.width == self.width && seed.height == self.height {
+            self.replace_cells(seed.cells);
+        } else {
+            self.width = seed.width;
+            self.height = seed.height;
+            self.cells = seed.cells;
+            self.changed_cells.clear();
+            self.rebuild_live_cells();
+        }
+    }
+
+    // state of the cell at (row, col); anything off the board reads as dead
+    pub fn get_cell(&self, row: u32, col: u32) -> Cell {
+        if row < self.height && col < self.width {
+            self.cells[self.get_index(row, col)]
+        } else {
+            Cell::Dead
+        }
+    }
+
+    /// Live neighbors of the cell at (row, col) under the current boundary
+    /// and neighborhood; 0 for anything off the board.
+    ///
+    /// ```
+    /// use life::game_of_life::Universe;
+    ///
+    /// let universe = Universe::from_plaintext("...O\n....\n....\nO..O").unwrap();
+    /// // on a torus the top-left corner touches the other three corners
+    /// assert_eq!(universe.neighbor_count(0, 0), 3);
+    /// ```
+    pub fn neighbor_count(&self, row: u32, col: u32) -> u8 {
+        if row < self.height && col < self.width {
+            self.live_neighbor_count(row, col)
+        } else {
+            0
+        }
+    }
+
+    // how many generations the cell at (row, col) has stayed alive; 0 for a
+    // newborn and for anything not alive
+    pub fn age_at(&self, row: u32, col: u32) -> u32 {
+        if row < self.height && col < self.width {
+            self.ages[self.get_index(row, col)]
+        } else {
+            0
+        }
+    }
+
+    // Immigration color of the cell at (row, col); 0 unless it's alive
+    pub fn color_at(&self, row: u32, col: u32) -> u8 {
+        if row < self.height && col < self.width {
+            self.colors[self.get_index(row, col)]
+        } else {
+            0
+        }
+    }
+
+    // colors the live cell at (row, col); births take the majority color of
+    // their parents, so a few colored seeds spread through the board
+    pub fn set_color(&mut self, row: u32, col: u32, color: u8) {
+        if self.get_cell(row, col) == Cell::Alive {
+            let idx = self.get_index(row, col);
+            self.colors[idx] = color;
+            self.colored |= color != 0;
+        }
+    }
+
+    // sets the cell at (row, col), ignoring coordinates off the board
+    pub fn set_cell(&mut self, row: u32, col: u32, state: Cell) {
+        if row >= self.height || col >= self.width {
+            return;
+        }
+        let idx = self.get_index(row, col);
+        if self.cells[idx] == state {
+            return;
+        }
+        self.cells[idx] = state;
+        self.colors[idx] = 0;
+        self.ages[idx] = 0;
+        self.changed_cells.push((col, row));
+        self.stable = false;
+        if state == Cell::Alive {
+            self.live_cells.push((col, row));
+        } else {
+            self.live_cells.retain(|&cell| cell != (col, row));
+        }
+    }
+
+    pub fn toggle_cell(&mut self, row: u32, col: u32) {
+        let state = match self.get_cell(row, col) {
+            Cell::Alive => Cell::Dead,
+            _ => Cell::Alive,
+        };
+        self.set_cell(row, col, state);
+    }
+
+    // stamps live (row, col) cells translated to the origin; cells that land
+    // off the board wrap on a torus and are dropped with dead boundaries
+    pub fn insert_pattern(&mut self, cells: &[(u32, u32)], origin_row: u32, origin_col: u32) {
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+        for &(row, col) in cells {
+            let row = origin_row as u64 + row as u64;
+            let col = origin_col as u64 + col as u64;
+            let (row, col) = match self.boundary {
+                Boundary::Toroidal => (row % self.height as u64, col % self.width as u64),
+                Boundary::Dead => (row, col),
+            };
+            if row < self.height as u64 && col < self.width as u64 {
+                self.set_cell(row as u32, col as u32, Cell::Alive);
+            }
+        }
+    }
+
+    // changes the board size, keeping the overlapping top-left region and
+    // filling any new area with dead cells
+    pub fn resize(&mut self, new_width: u32, new_height: u32) {
+        self.remap(new_width, new_height, |row, col| {
+            if row < new_height && col < new_width {
+                Some((row, col))
+            } else {
+                None
+            }
+        });
+    }
+
+    // moves the live pattern so its bounding box sits in the middle of the
+    // board
+    pub fn center(&mut self) {
+        if let Some((min_row, min_col, max_row, max_col)) = self.bounding_box() {
+            let top = (self.height - (max_row - min_row + 1)) / 2;
+            let left = (self.width - (max_col - min_col + 1)) / 2;
+            let wrap = self.boundary == Boundary::Toroidal;
+            self.translate(
+                top as i64 - min_row as i64,
+                left as i64 - min_col as i64,
+                wrap,
+            );
+        }
+    }
+
+    // moves the whole board by (dr, dc), wrapping around the edges
+    pub fn shift(&mut self, dr: i32, dc: i32) {
+        self.translate(dr as i64, dc as i64, true);
+    }
+
+    // sets every cell in the inclusive rectangle between the two corners,
+    // clamped to the board
+    pub fn fill_rect(&mut self, r0: u32, c0: u32, r1: u32, c1: u32, state: Cell) {
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+        let (top, bottom) = (r0.min(r1), r0.max(r1).min(self.height - 1));
+        let (left, right) = (c0.min(c1), c0.max(c1).min(self.width - 1));
+        let mut cells = self.cells.clone();
+        for row in top..=bottom {
+            for col in left..=right {
+                cells[self.get_index(row, col)] = state;
+            }
+        }
+        self.replace_cells(cells);
+    }
+
+    /// Sets every cell on the straight line between the two cells, both
+    /// ends included, using Bresenham's algorithm. Cells that fall off the
+    /// board are skipped.
+    ///
+    /// ```
+    /// use life::{Cell, Universe};
+    ///
+    /// let mut board = Universe::from_plaintext("....\n....\n....").unwrap();
+    /// board.draw_line(0, 0, 2, 3, Cell::Alive);
+    /// assert_eq!(board.to_plaintext(), "O...\n.OO.\n...O\n");
+    /// ```
+    pub fn draw_line(&mut self, r0: u32, c0: u32, r1: u32, c1: u32, state: Cell) {
+        for (row, col) in line_cells(r0, c0, r1, c1) {
+            self.set_cell(row, col, state);
+        }
+    }
+
+    /// A new board holding just the inclusive rectangle between the two
+    /// corners, clamped to this one, with its rule, boundary and
+    /// neighborhood.
+    ///
+    /// ```
+    /// use life::Universe;
+    ///
+    /// let board = Universe::from_plaintext("O..O\n.OO.\n.O..\nO..O").unwrap();
+    /// let center = board.subgrid(1, 1, 2, 2);
+    /// assert_eq!((center.width(), center.height()), (2, 2));
+    /// assert_eq!(center.to_plaintext(), "OO\nO.\n");
+    /// ```
+    pub fn subgrid(&self, r0: u32, c0: u32, r1: u32, c1: u32) -> Universe {
+        if self.width == 0 || self.height == 0 {
+            return Universe::with_cells(0, 0, Vec::new());
+        }
+        let (top, bottom) = (r0.min(r1), r0.max(r1).min(self.height - 1));
+        let (left, right) = (c0.min(c1), c0.max(c1).min(self.width - 1));
+        let (top, left) = (top.min(bottom), left.min(right));
+        let mut cells = Vec::with_capacity(((bottom - top + 1) * (right - left + 1)) as usize);
+        let mut colors = Vec::with_capacity(cells.capacity());
+        for row in top..=bottom {
+            for col in left..=right {
+                let index = self.get_index(row, col);
+                cells.push(self.cells[index]);
+                colors.push(self.colors[index]);
+            }
+        }
 
+        let mut universe = Universe::with_cells(right - left + 1, bottom - top + 1, cells);
+        universe.colors = colors;
+        universe.colored = self.colored;
+        universe.rule = self.rule;
+        universe.boundary = self.boundary;
+        universe.neighborhood = self.neighborhood;
+        universe
+    }
+
+    // swaps live and dead cells; dying cells are left as they are
+    pub fn invert(&mut self) {
+        let cells = self
+            .cells
+            .iter()
+            .map(|&cell| match cell {
+                Cell::Alive => Cell::Dead,
+                Cell::Dead => Cell::Alive,
+                dying => dying,
+            })
+            .collect();
+        self.replace_cells(cells);
+    }
+
+    // mirrors the board left to right
+    pub fn flip_horizontal(&mut self) {
+        let width = self.width;
+        self.remap(self.width, self.height, |row, col| {
+            Some((row, width - 1 - col))
+        });
+    }
+
+    // mirrors the board top to bottom
+    pub fn flip_vertical(&mut self) {
+        let height = self.height;
+        self.remap(self.width, self.height, |row, col| {
+            Some((height - 1 - row, col))
+        });
+    }
+
+    // rotates the board a quarter turn clockwise; a board that isn't square
+    // swaps its width and height
+    pub fn rotate_90(&mut self) {
+        let height = self.height;
+        self.remap(self.height, self.width, |row, col| {
+            Some((col, height - 1 - row))
+        });
+    }
+
+    // moves every cell by (delta_row, delta_col), either wrapping around the
+    // edges or dropping whatever is pushed off the board
+    fn translate(&mut self, delta_row: i64, delta_col: i64, wrap: bool) {
+        let (height, width) = (self.height as i64, self.width as i64);
+        self.remap(self.width, self.height, |row, col| {
+            let mut new_row = row as i64 + delta_row;
+            let mut new_col = col as i64 + delta_col;
+            if wrap {
+                new_row = new_row.rem_euclid(height);
+                new_col = new_col.rem_euclid(width);
+            } else if new_row < 0 || new_row >= height || new_col < 0 || new_col >= width {
+                return None;
+            }
+            Some((new_row as u32, new_col as u32))
+        });
+    }
+
+    // rebuilds the board at `width` x `height`, moving each cell (and its
+    // color) to wherever `target` sends it; cells sent to None are dropped
+    // and anything nothing lands on is dead
+    fn remap<F>(&mut self, width: u32, height: u32, target: F)
+    where
+        F: Fn(u32, u32) -> Option<(u32, u32)>,
+    {
+        let mut cells = vec![Cell::Dead; (width * height) as usize];
+        let mut colors = vec![0; cells.len()];
+        let mut ages = vec![0; cells.len()];
         for row in 0..self.height {
             for col in 0..self.width {
                 let idx = self.get_index(row, col);
-                let cell = self.cells[idx];
-                let live_neighbors = self.live_neighbor_count(row, col);
-
-                let next_cell = match (cell, live_neighbors) {
-                    // Rule 1: Any live cell with fewer than two live neighbors
-                    // dies, as if caused by underpopulation.
-                    (Cell::Alive, x) if x < 2 => Cell::Dead,
-                    // Rule 2: Any live cell with two or three live neighbors
-                    // lives on to the next generation.
-                    (Cell::Alive, 2) | (Cell::Alive, 3) => {
-                        self.live_cells.push((col, row));
-                        Cell::Alive
-                    }
-                    // Rule 3: Any live cell with more than three live
-                    // neighbors dies, as if by overpopulation.
-                    (Cell::Alive, x) if x > 3 => Cell::Dead,
-                    // Rule 4: Any dead cell with exactly three live neighbors
-                    // becomes a live cell, as if by reproduction.
-                    (Cell::Dead, 3) => {
-                        self.live_cells.push((col, row));
-                        Cell::Alive
+                if self.cells[idx] == Cell::Dead {
+                    continue;
+                }
+                if let Some((new_row, new_col)) = target(row, col) {
+                    let new_idx = (new_row * width + new_col) as usize;
+                    cells[new_idx] = self.cells[idx];
+                    colors[new_idx] = self.colors[idx];
+                    ages[new_idx] = self.ages[idx];
+                }
+            }
+        }
+
+        self.colors = colors;
+        self.ages = ages;
+        if width == self.width && height == self.height {
+            self.replace_cells(cells);
+        } else {
+            self.width = width;
+            self.height = height;
+            self.cells = cells;
+            self.changed_cells.clear();
+            self.stable = false;
+            // earlier generations no longer fit the board
+            self.history.clear();
+            self.rebuild_live_cells();
+        }
+    }
+
+    // swaps in a whole new grid, recording which cells differ
+    fn replace_cells(&mut self, cells: Vec<Cell>) {
+        self.changed_cells.clear();
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let idx = self.get_index(row, col);
+                if cells[idx] != self.cells[idx] {
+                    self.changed_cells.push((col, row));
+                    self.ages[idx] = 0;
+                    if cells[idx] != Cell::Alive {
+                        self.colors[idx] = 0;
                     }
-                    (Cell::Alive, _) => {
-                        self.live_cells.push((col, row));
-                        Cell::Alive
+                }
+            }
+        }
+        self.cells = cells;
+        self.stable = false;
+        self.rebuild_live_cells();
+    }
+
+    // kill every cell in place, keeping the allocation
+    pub fn clear(&mut self) {
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let idx = self.get_index(row, col);
+                if self.cells[idx] != Cell::Dead {
+                    self.cells[idx] = Cell::Dead;
+                    self.colors[idx] = 0;
+                    self.ages[idx] = 0;
+                    self.changed_cells.push((col, row));
+                }
+            }
+        }
+        self.live_cells.clear();
+        self.stable = false;
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    pub fn set_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+    }
+
+    pub fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    pub fn set_boundary(&mut self, boundary: Boundary) {
+        self.boundary = boundary;
+    }
+
+    pub fn neighborhood(&self) -> Neighborhood {
+        self.neighborhood
+    }
+
+    pub fn set_neighborhood(&mut self, neighborhood: Neighborhood) {
+        self.neighborhood = neighborhood;
+    }
+
+    pub fn noise(&self) -> f64 {
+        self.noise
+    }
+
+    /// Makes every update flip each cell with probability `noise`, clamped
+    /// to 0.0..=1.0, after the rule has run, using a generator seeded with
+    /// `seed`. At 0.0 updates are exactly the rule's.
+    ///
+    /// ```
+    /// use life::Universe;
+    ///
+    /// let (mut noiseless, mut plain) = (
+    ///     Universe::new_random(32, 32, 0.3, 9),
+    ///     Universe::new_random(32, 32, 0.3, 9),
+    /// );
+    /// noiseless.set_noise(0.0, 42);
+    /// for _ in 0..20 {
+    ///     noiseless.update();
+    ///     plain.update();
+    /// }
+    /// assert_eq!(noiseless.to_plaintext(), plain.to_plaintext());
+    ///
+    /// // flipping everything undoes the rule entirely
+    /// let (mut flipped, mut plain) = (
+    ///     Universe::new_random(32, 32, 0.3, 9),
+    ///     Universe::new_random(32, 32, 0.3, 9),
+    /// );
+    /// flipped.set_noise(1.0, 42);
+    /// flipped.update();
+    /// plain.update();
+    /// plain.invert();
+    /// assert_eq!(flipped.to_plaintext(), plain.to_plaintext());
+    /// ```
+    pub fn set_noise(&mut self, noise: f64, seed: u64) {
+        self.noise = noise.clamp(0.0, 1.0);
+        self.noise_rng = XorShift::new(seed);
+    }
+
+    // flips each live or dead cell of the next generation with probability
+    // `noise`
+    fn add_noise(&mut self, next: &mut [Cell]) {
+        if self.noise <= 0.0 {
+            return;
+        }
+        for cell in next.iter_mut() {
+            if self.noise_rng.next_f64() < self.noise {
+                *cell = match *cell {
+                    Cell::Alive => Cell::Dead,
+                    Cell::Dead => Cell::Alive,
+                    // noise is between live and dead; dying cells carry on
+                    dying => dying,
+                };
+            }
+        }
+    }
+
+    // true once an update has run without changing a single cell
+    pub fn is_stable(&self) -> bool {
+        self.stable
+    }
+
+    // (col, row) of every cell flipped by the last update, plus any edits
+    // made since then
+    pub fn changed_cells(&self) -> &[(u32, u32)] {
+        &self.changed_cells
+    }
+
+    // forgets the changed cells once a renderer has caught up with them
+    pub fn clear_changed_cells(&mut self) {
+        self.changed_cells.clear();
+    }
+
+    // 64 bit FNV-1a hash of the cell grid, for spotting repeated states
+    pub fn state_hash(&self) -> u64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for cell in self.cells.iter() {
+            hash ^= cell.state() as u64;
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+        hash
+    }
+
+    // cells that came to life in the last update
+    pub fn last_births(&self) -> usize {
+        self.last_births
+    }
+
+    // live cells that stopped living in the last update
+    pub fn last_deaths(&self) -> usize {
+        self.last_deaths
+    }
+
+    // steps up to `max_steps` generations looking for a state seen before,
+    // returning the length of the cycle (1 for a still life, 2 for a blinker)
+    pub fn detect_period(&mut self, max_steps: usize) -> Option<usize> {
+        self.find_cycle(max_steps)
+            .map(|(first_seen, step)| step - first_seen)
+    }
+
+    /// Steps up to `max_steps` generations until the board repeats a state,
+    /// returning how many generations in the repeating part began: 0 for
+    /// still lifes and oscillators, the lifespan for a methuselah. The board
+    /// is left one cycle past that point.
+    ///
+    /// ```
+    /// use life::Universe;
+    ///
+    /// // three cells of a block, which fill in the fourth
+    /// let mut pre_block = Universe::from_plaintext("....\n.O..\n.OO.\n....").unwrap();
+    /// assert_eq!(pre_block.run_until_stable(10), Some(1));
+    ///
+    /// // a pair of cells dies of loneliness
+    /// let mut domino = Universe::from_plaintext("....\n.OO.\n....").unwrap();
+    /// assert_eq!(domino.run_until_stable(10), Some(1));
+    ///
+    /// let mut blinker = Universe::from_plaintext(".....\n.....\n.OOO.\n.....\n.....").unwrap();
+    /// assert_eq!(blinker.run_until_stable(10), Some(0));
+    /// assert_eq!(blinker.run_until_stable(1), None);
+    /// ```
+    pub fn run_until_stable(&mut self, max_steps: usize) -> Option<usize> {
+        self.find_cycle(max_steps).map(|(first_seen, _)| first_seen)
+    }
+
+    // (the step a repeated state was first seen at, the step it came back
+    // at), counting from the current generation
+    fn find_cycle(&mut self, max_steps: usize) -> Option<(usize, usize)> {
+        let mut seen: HashMap<u64, usize> = HashMap::new();
+        seen.insert(self.state_hash(), 0);
+        for step in 1..=max_steps {
+            self.update();
+            if let Some(first_seen) = seen.insert(self.state_hash(), step) {
+                return Some((first_seen, step));
+            }
+        }
+        None
+    }
+
+    /// Runs a copy of the board for `2 * max_period` generations (at most
+    /// 64) and gives, row-major, the period of the group of cells each
+    /// cell belongs to: 1 for still lifes, the oscillator's period for
+    /// oscillators, 0 for cells that were never alive and for groups that
+    /// didn't repeat, like moving spaceships or chaos. A group is the
+    /// cells alive at some point in the run that touch, and its period is
+    /// the least common multiple of its cells' own.
+    ///
+    /// ```
+    /// use life::Universe;
+    ///
+    /// let board = Universe::from_plaintext(
+    ///     "........\n\
+    ///      .OOO....\n\
+    ///      ........\n\
+    ///      .....OO.\n\
+    ///      .....OO.\n\
+    ///      ........",
+    /// )
+    /// .unwrap();
+    /// let periods = board.oscillator_periods(8);
+    /// let at = |row: usize, col: usize| periods[row * 8 + col];
+    /// assert_eq!((at(1, 2), at(0, 2), at(2, 2)), (2, 2, 2));
+    /// assert_eq!(at(3, 5), 1);
+    /// assert_eq!(at(0, 7), 0);
+    /// ```
+    pub fn oscillator_periods(&self, max_period: usize) -> Vec<u32> {
+        let max_period = max_period.clamp(1, 32);
+        let samples = 2 * max_period;
+        // bit t of a cell's history is whether it was alive t generations in
+        let mut history = vec![0u64; self.cells.len()];
+        let mut universe = self.clone();
+        for t in 0..samples {
+            for (bits, &cell) in history.iter_mut().zip(universe.cells.iter()) {
+                if cell == Cell::Alive {
+                    *bits |= 1 << t;
+                }
+            }
+            universe.update();
+        }
+        let cell_period = |bits: u64| {
+            (1..=max_period)
+                .find(|&p| (bits ^ (bits >> p)) & ((1u64 << (samples - p)) - 1) == 0)
+                .map_or(0, |p| p as u32)
+        };
+
+        let mut periods = vec![0; self.cells.len()];
+        let mut visited = vec![false; self.cells.len()];
+        for start in 0..self.cells.len() {
+            if visited[start] || history[start] == 0 {
+                continue;
+            }
+            // flood fill the group, then give all of it the group's period
+            visited[start] = true;
+            let mut group = vec![start];
+            let mut next = 0;
+            let mut period = 1;
+            while next < group.len() {
+                let idx = group[next];
+                next += 1;
+                period = match (period, cell_period(history[idx])) {
+                    (0, _) | (_, 0) => 0,
+                    (a, b) => a / gcd(a, b) * b,
+                };
+                let (row, col) = (idx as u32 / self.width, idx as u32 % self.width);
+                for neighbor in self.neighbors(row, col) {
+                    if !visited[neighbor] && history[neighbor] != 0 {
+                        visited[neighbor] = true;
+                        group.push(neighbor);
                     }
-                    // All other cells remain in the same state.
-                    (otherwise, _) => otherwise,
+                }
+            }
+            for idx in group {
+                periods[idx] = period;
+            }
+        }
+        periods
+    }
+
+    // steps one generation, returning whether any cell changed
+    pub fn update(&mut self) -> bool {
+        let mut next = self.take_next_buffer();
+
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let idx = self.get_index(row, col);
+                next[idx] = self.next_state(row, col);
+            }
+        }
+
+        self.advance(next)
+    }
+
+    // same as `update`, but computes the rows of the next generation in
+    // parallel; only worth it on large boards
+    pub fn update_parallel(&mut self) -> bool {
+        let mut next = self.take_next_buffer();
+        let universe = &*self;
+
+        next.par_chunks_mut(self.width.max(1) as usize)
+            .enumerate()
+            .for_each(|(row, cells)| {
+                for (col, cell) in cells.iter_mut().enumerate() {
+                    *cell = universe.next_state(row as u32, col as u32);
+                }
+            });
+
+        self.advance(next)
+    }
+
+    // the spare buffer the next generation is written into, so stepping
+    // doesn't allocate; every entry gets overwritten
+    fn take_next_buffer(&mut self) -> Vec<Cell> {
+        let mut next = std::mem::take(&mut self.next_cells);
+        next.resize(self.cells.len(), Cell::Dead);
+        next
+    }
+
+    fn next_state(&self, row: u32, col: u32) -> Cell {
+        let cell = self.cells[self.get_index(row, col)];
+        let live_neighbors = self.live_neighbor_count(row, col);
+
+        match cell {
+            // A live cell lives on if its neighbor count is one the rule lets
+            // survive, otherwise it starts dying.
+            Cell::Alive if self.rule.survives(live_neighbors) => Cell::Alive,
+            Cell::Alive => self.decay(1),
+            // A dying cell keeps ageing regardless of its neighbors.
+            Cell::Dying(state) => self.decay(state),
+            // A dead cell comes to life if its neighbor count is one the rule
+            // gives birth on.
+            Cell::Dead if self.rule.is_born(live_neighbors) => Cell::Alive,
+            Cell::Dead => Cell::Dead,
+        }
+    }
+
+    // the state after `state` under the rule; with the usual two states a
+    // cell that stops living is simply dead
+    fn decay(&self, state: u8) -> Cell {
+        if state + 1 < self.rule.states() {
+            Cell::Dying(state + 1)
+        } else {
+            Cell::Dead
+        }
+    }
+
+    // keeps up to `depth` previous generations for `step_back`; 0 turns
+    // history off and drops anything recorded
+    pub fn enable_history(&mut self, depth: usize) {
+        self.history_depth = depth;
+        while self.history.len() > depth {
+            self.history.pop_front();
+        }
+    }
+
+    // restores the previous generation, if history has one
+    pub fn step_back(&mut self) -> bool {
+        let previous = match self.history.pop_back() {
+            Some(previous) => previous,
+            None => return false,
+        };
+
+        self.replace_cells(previous);
+        self.generation = self.generation.saturating_sub(1);
+        true
+    }
+
+    // installs `next` as the current generation, rebuilding the live and
+    // changed cell caches against the outgoing one, which becomes the spare
+    // buffer for the following step
+    fn advance(&mut self, mut next: Vec<Cell>) -> bool {
+        self.add_noise(&mut next);
+        if self.generation == 0 {
+            self.seed = Some(Seed {
+                width: self.width,
+                height: self.height,
+                cells: self.cells.clone(),
+                colors: self.colors.clone(),
+            });
+        }
+        if self.history_depth > 0 {
+            if self.history.len() == self.history_depth {
+                self.history.pop_front();
+            }
+            self.history.push_back(self.cells.clone());
+        }
+
+        self.live_cells.clear();
+        self.changed_cells.clear();
+        self.last_births = 0;
+        self.last_deaths = 0;
+
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let idx = self.get_index(row, col);
+                if next[idx] == Cell::Alive {
+                    self.live_cells.push((col, row));
+                }
+                self.ages[idx] = if next[idx] == Cell::Alive && self.cells[idx] == Cell::Alive {
+                    self.ages[idx] + 1
+                } else {
+                    0
                 };
+                if next[idx] != self.cells[idx] {
+                    self.changed_cells.push((col, row));
+                    if next[idx] == Cell::Alive {
+                        self.last_births += 1;
+                    } else if self.cells[idx] == Cell::Alive {
+                        self.last_deaths += 1;
+                    }
+                }
+            }
+        }
 
-                next[idx] = next_cell;
+        // newborns take the majority color of their parents, so every birth
+        // is colored before any parent that died loses its color
+        if self.colored {
+            let birth_colors: Vec<(usize, u8)> = self
+                .changed_cells
+                .iter()
+                .map(|&(col, row)| (col, row, self.get_index(row, col)))
+                .filter(|&(_, _, idx)| next[idx] == Cell::Alive)
+                .map(|(col, row, idx)| (idx, self.majority_color(row, col)))
+                .collect();
+            for &(col, row) in self.changed_cells.iter() {
+                let idx = (row * self.width + col) as usize;
+                self.colors[idx] = 0;
+            }
+            for (idx, color) in birth_colors {
+                self.colors[idx] = color;
             }
         }
 
-        self.cells = next;
+        self.next_cells = std::mem::replace(&mut self.cells, next);
+        self.generation += 1;
+        self.stable = self.changed_cells.is_empty();
+        !self.stable
     }
 
     pub fn new(width: u32, height: u32) -> Universe {
@@ -89,22 +1067,835 @@ impl Universe {
                 }
             })
             .collect();
-        let mut live_cells: Vec<(u32, u32)> = Vec::new();
-        for row in 0..height {
-            for col in 0..width {
-                let idx = (row * width + col) as usize;
-                let cell = cells[idx];
-                if cell == Cell::Alive {
-                    live_cells.push((col, row))
+        Universe::with_cells(width, height, cells)
+    }
+
+    // same starting board as `new`, evolved under `rule` instead of Conway's
+    pub fn with_rule(width: u32, height: u32, rule: Rule) -> Universe {
+        let mut universe = Universe::new(width, height);
+        universe.rule = rule;
+        universe
+    }
+
+    // random soup where each cell is alive with probability `density`;
+    // the same seed always produces the same board
+    pub fn new_random(width: u32, height: u32, density: f64, seed: u64) -> Universe {
+        let mut rng = XorShift::new(seed);
+        let cells: Vec<Cell> = (0..width * height)
+            .map(|_| {
+                if rng.next_f64() < density {
+                    Cell::Alive
+                } else {
+                    Cell::Dead
                 }
+            })
+            .collect();
+        Universe::with_cells(width, height, cells)
+    }
+
+    // one row per inner Vec, `true` for alive; every row must be the same
+    // length
+    pub fn from_bool_grid(grid: &[Vec<bool>]) -> Result<Universe, ParseError> {
+        let height = grid.len() as u32;
+        let width = grid.first().map_or(0, |row| row.len()) as u32;
+        let mut cells = Vec::with_capacity((width * height) as usize);
+        for (row, line) in grid.iter().enumerate() {
+            if line.len() != width as usize {
+                return Err(ParseError::new(format!(
+                    "row {} has {} cells, expected {}",
+                    row,
+                    line.len(),
+                    width
+                )));
             }
+            cells.extend(
+                line.iter()
+                    .map(|&alive| if alive { Cell::Alive } else { Cell::Dead }),
+            );
         }
+        Ok(Universe::with_cells(width, height, cells))
+    }
+
+    // row-major live/dead flags, the inverse of `from_bool_grid`
+    pub fn to_bool_grid(&self) -> Vec<Vec<bool>> {
+        self.cells
+            .chunks(self.width.max(1) as usize)
+            .map(|row| row.iter().map(|&cell| cell == Cell::Alive).collect())
+            .collect()
+    }
 
-        Universe {
+    pub(crate) fn with_cells(width: u32, height: u32, cells: Vec<Cell>) -> Universe {
+        let colors = vec![0; cells.len()];
+        let ages = vec![0; cells.len()];
+        let mut universe = Universe {
             width,
             height,
             cells,
-            live_cells,
+            next_cells: Vec::new(),
+            colors,
+            colored: false,
+            ages,
+            live_cells: Vec::new(),
+            changed_cells: Vec::new(),
+            stable: false,
+            last_births: 0,
+            last_deaths: 0,
+            generation: 0,
+            rule: Rule::default(),
+            boundary: Boundary::Toroidal,
+            neighborhood: Neighborhood::Moore,
+            history: VecDeque::new(),
+            history_depth: 0,
+            seed: None,
+            noise: 0.0,
+            noise_rng: XorShift::new(0),
+        };
+        universe.rebuild_live_cells();
+        universe
+    }
+
+    // recomputes the live cell cache from scratch after bulk changes to cells
+    fn rebuild_live_cells(&mut self) {
+        self.live_cells.clear();
+        for row in 0..self.height {
+            for col in 0..self.width {
+                if self.cells[self.get_index(row, col)] == Cell::Alive {
+                    self.live_cells.push((col, row));
+                }
+            }
+        }
+    }
+}
+
+// Two universes are equal when their boards are, whatever their settings,
+// history or generation count.
+impl PartialEq for Universe {
+    fn eq(&self, other: &Universe) -> bool {
+        self.width == other.width && self.height == other.height && self.cells == other.cells
+    }
+}
+
+impl Eq for Universe {}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    width: u32,
+    height: u32,
+    cells: Vec<Cell>,
+    #[serde(default)]
+    colors: Vec<u8>,
+    generation: u64,
+    rule: Rule,
+    boundary: Boundary,
+    neighborhood: Neighborhood,
+}
+
+#[cfg(feature = "serde")]
+impl From<Universe> for Snapshot {
+    fn from(universe: Universe) -> Snapshot {
+        Snapshot {
+            width: universe.width,
+            height: universe.height,
+            cells: universe.cells,
+            colors: universe.colors,
+            generation: universe.generation,
+            rule: universe.rule,
+            boundary: universe.boundary,
+            neighborhood: universe.neighborhood,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<Snapshot> for Universe {
+    type Error = String;
+
+    fn try_from(snapshot: Snapshot) -> Result<Universe, String> {
+        let len = snapshot.width.checked_mul(snapshot.height);
+        if len.map(|len| len as usize) != Some(snapshot.cells.len()) {
+            return Err(format!(
+                "{} cells don't fill a {}x{} board",
+                snapshot.cells.len(),
+                snapshot.width,
+                snapshot.height
+            ));
+        }
+        let mut universe = Universe::with_cells(snapshot.width, snapshot.height, snapshot.cells);
+        if snapshot.colors.len() == universe.colors.len() {
+            universe.colored = snapshot.colors.iter().any(|&color| color != 0);
+            universe.colors = snapshot.colors;
+        }
+        universe.generation = snapshot.generation;
+        universe.rule = snapshot.rule;
+        universe.boundary = snapshot.boundary;
+        universe.neighborhood = snapshot.neighborhood;
+        Ok(universe)
+    }
+}
+
+// (row, col) of every cell on the straight line between two cells, both ends
+// included, by Bresenham's algorithm
+pub fn line_cells(r0: u32, c0: u32, r1: u32, c1: u32) -> Vec<(u32, u32)> {
+    let (mut row, mut col) = (r0 as i64, c0 as i64);
+    let (end_row, end_col) = (r1 as i64, c1 as i64);
+    let (d_row, d_col) = ((end_row - row).abs(), -(end_col - col).abs());
+    let step_row = if row < end_row { 1 } else { -1 };
+    let step_col = if col < end_col { 1 } else { -1 };
+    let mut error = d_row + d_col;
+    let mut cells = Vec::with_capacity((d_row - d_col + 1) as usize);
+    loop {
+        cells.push((row as u32, col as u32));
+        if row == end_row && col == end_col {
+            return cells;
+        }
+        let doubled = 2 * error;
+        if doubled >= d_col {
+            error += d_col;
+            row += step_row;
+        }
+        if doubled <= d_row {
+            error += d_row;
+            col += step_col;
+        }
+    }
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(cells: impl Iterator<Item = (u32, u32)>) -> Vec<(u32, u32)> {
+        let mut cells: Vec<(u32, u32)> = cells.collect();
+        cells.sort_unstable();
+        cells
+    }
+
+    fn empty(width: u32, height: u32) -> Universe {
+        Universe::with_cells(width, height, vec![Cell::Dead; (width * height) as usize])
+    }
+
+    // an empty board with the glider's top-left corner at (1, 1)
+    fn glider(width: u32, height: u32) -> Universe {
+        let mut universe = empty(width, height);
+        universe.insert_pattern(&crate::patterns::glider(), 1, 1);
+        universe
+    }
+
+    // live (row, col) cells of a pattern drawn with `O`s
+    fn pattern(rows: &[&str]) -> Vec<(u32, u32)> {
+        let mut cells = Vec::new();
+        for (row, line) in rows.iter().enumerate() {
+            for (col, c) in line.chars().enumerate() {
+                if c == 'O' {
+                    cells.push((row as u32, col as u32));
+                }
+            }
+        }
+        cells
+    }
+
+    #[test]
+    fn live_cells_match_a_seeded_grid() {
+        let mut cells = vec![Cell::Dead; 4 * 3];
+        for &(col, row) in &[(0, 0), (3, 0), (1, 1), (2, 2)] {
+            cells[row * 4 + col] = Cell::Alive;
+        }
+        let mut universe = Universe::with_cells(4, 3, cells);
+        assert_eq!(
+            sorted(universe.live_cells()),
+            vec![(0, 0), (1, 1), (2, 2), (3, 0)]
+        );
+
+        // the cache follows the board through an update
+        universe.update();
+        let expected = (0..3)
+            .flat_map(|row| (0..4).map(move |col| (col, row)))
+            .filter(|&(col, row)| universe.get_cell(row, col) == Cell::Alive);
+        assert_eq!(sorted(universe.live_cells()), sorted(expected));
+    }
+
+    #[test]
+    fn population_counts_live_cells() {
+        let mut universe = glider(8, 8);
+        assert_eq!(universe.population(), 5);
+        universe.update();
+        assert_eq!(universe.population(), 5);
+        universe.set_cell(7, 7, Cell::Alive);
+        assert_eq!(universe.population(), 6);
+    }
+
+    #[test]
+    fn generation_counts_updates() {
+        let mut universe = glider(8, 8);
+        assert_eq!(universe.generation(), 0);
+        for _ in 0..5 {
+            universe.update();
         }
+        assert_eq!(universe.generation(), 5);
+        universe.reset_generation();
+        assert_eq!(universe.generation(), 0);
+    }
+
+    #[test]
+    fn clear_empties_the_board() {
+        let mut universe = Universe::new_random(16, 16, 0.5, 3);
+        universe.clear();
+        assert_eq!(universe.population(), 0);
+        assert_eq!(universe.live_cells().count(), 0);
+        assert!(!universe.update());
+        assert_eq!(universe.population(), 0);
+    }
+
+    #[test]
+    fn new_random_is_reproducible() {
+        let a = Universe::new_random(32, 24, 0.4, 1234);
+        let b = Universe::new_random(32, 24, 0.4, 1234);
+        assert_eq!(a.cells, b.cells);
+        assert_ne!(a.cells, Universe::new_random(32, 24, 0.4, 1235).cells);
+
+        // nothing and everything alive at the extremes of density
+        assert_eq!(Universe::new_random(8, 8, 0.0, 1).population(), 0);
+        assert_eq!(Universe::new_random(8, 8, 1.0, 1).population(), 64);
+    }
+
+    #[test]
+    fn highlife_replicator_copies_itself() {
+        let replicator = pattern(&[
+            "..OOO", //
+            ".O..O", //
+            "O...O", //
+            "O..O.", //
+            "OOO..", //
+        ]);
+        let mut universe = empty(24, 24);
+        universe.set_rule(Rule::highlife());
+        universe.insert_pattern(&replicator, 10, 10);
+        let mut conway = universe.clone();
+        conway.set_rule(Rule::conway());
+        for _ in 0..12 {
+            universe.update();
+            conway.update();
+        }
+
+        // twelve generations later there are two of it, on either diagonal
+        let mut copies = empty(24, 24);
+        copies.insert_pattern(&replicator, 8, 8);
+        copies.insert_pattern(&replicator, 12, 12);
+        assert!(universe == copies);
+        assert!(conway != copies);
+    }
+
+    #[test]
+    fn corner_neighbors_depend_on_the_boundary() {
+        let mut universe = Universe::from_plaintext(".O.O\nOO..\n....\nO..O").unwrap();
+        // (0, 1), (1, 0) and (1, 1) touch the corner on the board, and on a
+        // torus (0, 3), (3, 0) and (3, 3) wrap around to it too
+        assert_eq!(universe.neighbor_count(0, 0), 6);
+        universe.set_boundary(Boundary::Dead);
+        assert_eq!(universe.neighbor_count(0, 0), 3);
+        assert_eq!(universe.neighbor_count(3, 3), 0);
+    }
+
+    #[test]
+    fn gliders_wrap_on_a_torus_and_die_at_dead_edges() {
+        let mut torus = glider(8, 8);
+        let mut walled = glider(8, 8);
+        walled.set_boundary(Boundary::Dead);
+        for _ in 0..32 {
+            torus.update();
+            walled.update();
+        }
+        // eight cells in 32 generations is once round the board
+        assert!(torus == glider(8, 8));
+        assert_eq!(walled.population(), 4);
+        assert!(walled.is_stable());
+    }
+
+    #[test]
+    fn set_get_and_toggle_cells() {
+        let mut universe = empty(4, 3);
+        universe.set_cell(1, 2, Cell::Alive);
+        assert_eq!(universe.get_cell(1, 2), Cell::Alive);
+        assert_eq!(universe.get_cell(2, 1), Cell::Dead);
+        assert_eq!(universe.changed_cells(), &[(2, 1)]);
+        assert_eq!(universe.population(), 1);
+
+        universe.toggle_cell(1, 2);
+        universe.toggle_cell(0, 0);
+        assert_eq!(universe.get_cell(1, 2), Cell::Dead);
+        assert_eq!(universe.get_cell(0, 0), Cell::Alive);
+        assert_eq!(sorted(universe.live_cells()), vec![(0, 0)]);
+        assert_eq!(universe.population(), 1);
+    }
+
+    #[test]
+    fn cells_off_the_board_are_ignored() {
+        let mut universe = empty(4, 3);
+        universe.set_cell(3, 0, Cell::Alive);
+        universe.set_cell(0, 4, Cell::Alive);
+        universe.toggle_cell(u32::MAX, u32::MAX);
+        assert_eq!(universe.population(), 0);
+        assert!(universe.changed_cells().is_empty());
+        assert_eq!(universe.get_cell(3, 0), Cell::Dead);
+        assert_eq!(universe.get_cell(0, 4), Cell::Dead);
+    }
+
+    #[test]
+    fn insert_pattern_offsets_cells() {
+        let mut universe = empty(10, 10);
+        universe.insert_pattern(&crate::patterns::blinker(), 5, 5);
+        assert_eq!(sorted(universe.live_cells()), vec![(5, 5), (6, 5), (7, 5)]);
+    }
+
+    #[test]
+    fn insert_pattern_wraps_or_clips_at_the_edge() {
+        let mut torus = empty(6, 6);
+        torus.insert_pattern(&crate::patterns::blinker(), 2, 4);
+        assert_eq!(sorted(torus.live_cells()), vec![(0, 2), (4, 2), (5, 2)]);
+
+        let mut walled = empty(6, 6);
+        walled.set_boundary(Boundary::Dead);
+        walled.insert_pattern(&crate::patterns::blinker(), 2, 4);
+        assert_eq!(sorted(walled.live_cells()), vec![(4, 2), (5, 2)]);
+    }
+
+    #[test]
+    fn still_lifes_are_stable_after_one_step() {
+        let mut block = Universe::from_plaintext("....\n.OO.\n.OO.\n....").unwrap();
+        assert!(!block.is_stable());
+        assert!(!block.update());
+        assert!(block.is_stable());
+        assert!(block.changed_cells().is_empty());
+
+        let mut blinker = Universe::from_plaintext(".....\n.....\n.OOO.\n.....\n.....").unwrap();
+        assert!(blinker.update());
+        assert!(!blinker.is_stable());
+        assert_eq!(blinker.changed_cells().len(), 4);
+    }
+
+    #[test]
+    fn detect_period_of_oscillators_and_still_lifes() {
+        let mut blinker = Universe::from_plaintext(".....\n.....\n.OOO.\n.....\n.....").unwrap();
+        assert_eq!(blinker.detect_period(10), Some(2));
+        let mut block = Universe::from_plaintext("....\n.OO.\n.OO.\n....").unwrap();
+        assert_eq!(block.detect_period(10), Some(1));
+        // on a torus a glider comes back once it has crossed the board
+        assert_eq!(glider(8, 8).detect_period(100), Some(32));
+        assert_eq!(glider(8, 8).detect_period(31), None);
+    }
+
+    #[test]
+    fn state_hash_tells_boards_apart() {
+        let universe = Universe::new_random(16, 16, 0.5, 8);
+        let mut other = universe.clone();
+        assert_eq!(universe.state_hash(), other.state_hash());
+        other.toggle_cell(3, 4);
+        assert_ne!(universe.state_hash(), other.state_hash());
+    }
+
+    #[test]
+    fn parallel_update_matches_sequential() {
+        for &boundary in &[Boundary::Toroidal, Boundary::Dead] {
+            let mut sequential = Universe::new_random(211, 157, 0.35, 21);
+            sequential.set_boundary(boundary);
+            let mut parallel = sequential.clone();
+            for generation in 0..60 {
+                assert_eq!(sequential.update(), parallel.update_parallel());
+                assert!(sequential == parallel, "{:?} gen {}", boundary, generation);
+                assert_eq!(sequential.live_cells, parallel.live_cells);
+                assert_eq!(sequential.changed_cells, parallel.changed_cells);
+            }
+        }
+    }
+
+    #[test]
+    fn reused_buffers_keep_the_caches_right() {
+        let mut universe = Universe::new_random(40, 30, 0.4, 5);
+        universe.update();
+        let buffers = |universe: &Universe| {
+            let mut buffers = [universe.cells.as_ptr(), universe.next_cells.as_ptr()];
+            buffers.sort_unstable();
+            buffers
+        };
+        let first = buffers(&universe);
+
+        for _ in 0..25 {
+            let previous = universe.cells.clone();
+            universe.update();
+            // the two grids trade places instead of being reallocated
+            assert_eq!(buffers(&universe), first);
+
+            // and the caches describe the new grid, against the old one
+            let live = (0..30)
+                .flat_map(|row| (0..40).map(move |col| (col, row)))
+                .filter(|&(col, row)| universe.get_cell(row, col) == Cell::Alive);
+            assert_eq!(sorted(universe.live_cells()), sorted(live));
+            let changed = (0..30)
+                .flat_map(|row| (0..40).map(move |col| (col, row)))
+                .filter(|&(col, row)| {
+                    previous[(row * 40 + col) as usize] != universe.get_cell(row, col)
+                });
+            assert_eq!(
+                sorted(universe.changed_cells().iter().cloned()),
+                sorted(changed)
+            );
+        }
+    }
+
+    #[test]
+    fn resize_keeps_the_top_left() {
+        let mut universe = Universe::from_plaintext("O.O\n.O.\nO..").unwrap();
+        universe.resize(5, 5);
+        assert_eq!((universe.width(), universe.height()), (5, 5));
+        assert_eq!(
+            universe.to_plaintext(),
+            "O.O..\n.O...\nO....\n.....\n.....\n"
+        );
+        assert_eq!(universe.population(), 4);
+
+        universe.resize(2, 2);
+        assert_eq!(universe.to_plaintext(), "O.\n.O\n");
+        assert_eq!(sorted(universe.live_cells()), vec![(0, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn brians_brain_cells_die_through_a_refractory_state() {
+        let mut universe = empty(6, 6);
+        universe.set_rule(Rule::brians_brain());
+        universe.set_cell(2, 2, Cell::Alive);
+        universe.set_cell(2, 3, Cell::Alive);
+
+        universe.update();
+        // the pair starts dying, and every cell touching both is born
+        assert_eq!(universe.get_cell(2, 2), Cell::Dying(2));
+        assert_eq!(universe.get_cell(2, 3), Cell::Dying(2));
+        let born = vec![(2, 1), (2, 3), (3, 1), (3, 3)];
+        assert_eq!(sorted(universe.live_cells()), born);
+        assert_eq!(universe.last_births(), 4);
+        assert_eq!(universe.last_deaths(), 2);
+
+        universe.update();
+        // dying cells are dead a generation later
+        assert_eq!(universe.get_cell(2, 2), Cell::Dead);
+        assert_eq!(universe.get_cell(2, 3), Cell::Dead);
+        for &(col, row) in &born {
+            assert_eq!(universe.get_cell(row, col), Cell::Dying(2));
+        }
+        // and never count as neighbors: beside the old pair two live cells
+        // and one dying one make a birth
+        assert_eq!(universe.get_cell(2, 1), Cell::Alive);
+        assert_eq!(universe.get_cell(2, 4), Cell::Alive);
+    }
+
+    #[test]
+    fn noise_leaves_dying_cells_alone() {
+        let mut plain = empty(6, 6);
+        plain.set_rule(Rule::brians_brain());
+        plain.set_cell(2, 2, Cell::Alive);
+        plain.set_cell(2, 3, Cell::Alive);
+        let mut noisy = plain.clone();
+        noisy.set_noise(1.0, 7);
+
+        plain.update();
+        noisy.update();
+        for row in 0..6 {
+            for col in 0..6 {
+                let expected = match plain.get_cell(row, col) {
+                    Cell::Alive => Cell::Dead,
+                    Cell::Dead => Cell::Alive,
+                    dying => dying,
+                };
+                assert_eq!(noisy.get_cell(row, col), expected, "({}, {})", row, col);
+            }
+        }
+        assert_eq!(noisy.get_cell(2, 2), Cell::Dying(2));
+        assert_eq!(noisy.population(), 36 - 2 - 4);
+    }
+
+    #[test]
+    fn step_back_restores_earlier_generations() {
+        let mut universe = glider(8, 8);
+        universe.enable_history(10);
+        let mut boards = vec![universe.cells.clone()];
+        for _ in 0..3 {
+            universe.update();
+            boards.push(universe.cells.clone());
+        }
+        assert!(universe.step_back());
+        assert!(universe.step_back());
+        assert_eq!(universe.cells, boards[1]);
+        assert_eq!(universe.generation(), 1);
+        // the caches are rebuilt for the restored board
+        let live = (0..8)
+            .flat_map(|row| (0..8).map(move |col| (col, row)))
+            .filter(|&(col, row)| boards[1][(row * 8 + col) as usize] == Cell::Alive);
+        assert_eq!(sorted(universe.live_cells()), sorted(live));
+    }
+
+    #[test]
+    fn history_is_bounded_and_optional() {
+        let mut universe = glider(8, 8);
+        assert!(!universe.step_back());
+        universe.update();
+        assert!(!universe.step_back());
+
+        universe.enable_history(2);
+        for _ in 0..5 {
+            universe.update();
+        }
+        assert!(universe.step_back());
+        assert!(universe.step_back());
+        assert!(!universe.step_back());
+        assert_eq!(universe.generation(), 4);
+
+        universe.update();
+        universe.enable_history(0);
+        assert!(!universe.step_back());
+    }
+
+    #[test]
+    fn von_neumann_ignores_diagonals() {
+        let mut universe = Universe::from_plaintext("O.O\n...\nO.O").unwrap();
+        universe.set_boundary(Boundary::Dead);
+        assert_eq!(universe.neighbor_count(1, 1), 4);
+        universe.set_neighborhood(Neighborhood::VonNeumann);
+        assert_eq!(universe.neighbor_count(1, 1), 0);
+        // the orthogonal neighbors still count
+        universe.set_cell(0, 1, Cell::Alive);
+        universe.set_cell(1, 2, Cell::Alive);
+        assert_eq!(universe.neighbor_count(1, 1), 2);
+    }
+
+    #[test]
+    fn bounding_box_of_live_cells() {
+        assert_eq!(empty(5, 5).bounding_box(), None);
+        let mut universe = empty(5, 5);
+        universe.set_cell(1, 3, Cell::Alive);
+        universe.set_cell(3, 1, Cell::Alive);
+        assert_eq!(universe.bounding_box(), Some((1, 1, 3, 3)));
+        universe.set_cell(1, 3, Cell::Dead);
+        assert_eq!(universe.bounding_box(), Some((3, 1, 3, 1)));
+    }
+
+    #[test]
+    fn center_moves_the_pattern_to_the_middle() {
+        for &boundary in &[Boundary::Toroidal, Boundary::Dead] {
+            let mut universe = empty(11, 9);
+            universe.set_boundary(boundary);
+            universe.insert_pattern(&crate::patterns::glider(), 0, 0);
+            universe.center();
+            // as much space above as below, and to the left as the right
+            let (min_row, min_col, max_row, max_col) = universe.bounding_box().unwrap();
+            assert_eq!((min_row, 8 - max_row), (3, 3));
+            assert_eq!((min_col, 10 - max_col), (4, 4));
+            assert_eq!(universe.population(), 5);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn snapshots_round_trip_through_json() {
+        let mut universe = Universe::new_random(13, 7, 0.4, 6);
+        universe.set_rule(Rule::highlife());
+        universe.set_boundary(Boundary::Dead);
+        universe.set_neighborhood(Neighborhood::VonNeumann);
+        let (col, row) = universe.live_cells().next().unwrap();
+        universe.set_color(row, col, 3);
+        universe.update();
+
+        let json = serde_json::to_string(&universe).unwrap();
+        let copy: Universe = serde_json::from_str(&json).unwrap();
+        assert!(copy == universe);
+        assert_eq!(copy.generation(), 1);
+        assert_eq!(copy.rule(), Rule::highlife());
+        assert_eq!(copy.boundary(), Boundary::Dead);
+        assert_eq!(copy.neighborhood(), Neighborhood::VonNeumann);
+        assert_eq!(copy.colors, universe.colors);
+        assert_eq!(sorted(copy.live_cells()), sorted(universe.live_cells()));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn snapshots_must_fill_their_board() {
+        let universe = Universe::new_random(4, 4, 0.5, 1);
+        let json = serde_json::to_string(&universe).unwrap();
+        let short = json.replace("\"width\":4", "\"width\":5");
+        assert_ne!(short, json);
+        assert!(serde_json::from_str::<Universe>(&short).is_err());
+        // 65536 * 65536 wraps to 0 in a u32, which mustn't pass for no cells
+        let overflow = r#"{"width":65536,"height":65536,"cells":[],"generation":0,
+            "rule":"B3/S23","boundary":"Toroidal","neighborhood":"Moore"}"#;
+        assert!(serde_json::from_str::<Universe>(overflow).is_err());
+    }
+
+    #[test]
+    fn universes_compare_by_their_boards() {
+        let a = Universe::new_random(12, 12, 0.5, 99);
+        let mut b = Universe::new_random(12, 12, 0.5, 99);
+        // settings don't take part
+        b.set_rule(Rule::seeds());
+        assert!(a == b);
+        b.toggle_cell(6, 6);
+        assert!(a != b);
+        // but the shape does, even with the same cells
+        assert!(empty(4, 2) != empty(2, 4));
+    }
+
+    #[test]
+    fn from_bool_grid_builds_a_block() {
+        let block = Universe::from_bool_grid(&[vec![true, true], vec![true, true]]).unwrap();
+        assert_eq!((block.width(), block.height()), (2, 2));
+        assert_eq!(block.population(), 4);
+        assert!((0..2).all(|row| (0..2).all(|col| block.get_cell(row, col) == Cell::Alive)));
+        assert!(Universe::from_bool_grid(&[vec![true, true], vec![true]]).is_err());
+        assert_eq!(Universe::from_bool_grid(&[]).unwrap().population(), 0);
+    }
+
+    #[test]
+    fn bool_grids_round_trip() {
+        let universe = Universe::new_random(9, 5, 0.5, 17);
+        let grid = universe.to_bool_grid();
+        assert_eq!((grid.len(), grid[0].len()), (5, 9));
+        assert_eq!(grid[2][7], universe.get_cell(2, 7) == Cell::Alive);
+        assert!(Universe::from_bool_grid(&grid).unwrap() == universe);
+    }
+
+    #[test]
+    fn blinker_flips_two_cells_each_way() {
+        let mut blinker = Universe::from_plaintext(".....\n.....\n.OOO.\n.....\n.....").unwrap();
+        assert_eq!((blinker.last_births(), blinker.last_deaths()), (0, 0));
+        for _ in 0..4 {
+            blinker.update();
+            assert_eq!((blinker.last_births(), blinker.last_deaths()), (2, 2));
+        }
+    }
+
+    #[test]
+    fn shift_wraps_around() {
+        let mut universe = empty(5, 4);
+        universe.set_cell(3, 4, Cell::Alive);
+        universe.shift(2, 1);
+        assert_eq!(sorted(universe.live_cells()), vec![(0, 1)]);
+        assert_eq!(
+            sorted(universe.changed_cells().iter().cloned()),
+            vec![(0, 1), (4, 3)]
+        );
+        universe.shift(-5, -6);
+        assert_eq!(sorted(universe.live_cells()), vec![(4, 0)]);
+    }
+
+    #[test]
+    fn four_quarter_turns_are_the_identity() {
+        for &(width, height) in &[(7, 7), (9, 4)] {
+            let original = Universe::new_random(width, height, 0.4, 23);
+            let mut universe = original.clone();
+            for turn in 1..=4 {
+                universe.rotate_90();
+                let expected = if turn % 2 == 0 {
+                    (width, height)
+                } else {
+                    (height, width)
+                };
+                assert_eq!((universe.width(), universe.height()), expected);
+            }
+            assert!(universe == original);
+        }
+    }
+
+    #[test]
+    fn transforms_move_cells_where_expected() {
+        let mut universe = Universe::from_plaintext("OO.\n...").unwrap();
+        universe.rotate_90();
+        assert_eq!(universe.to_plaintext(), ".O\n.O\n..\n");
+        universe.flip_horizontal();
+        assert_eq!(universe.to_plaintext(), "O.\nO.\n..\n");
+        universe.flip_vertical();
+        assert_eq!(universe.to_plaintext(), "..\nO.\nO.\n");
+        universe.flip_vertical();
+        universe.flip_horizontal();
+        assert_eq!(universe.to_plaintext(), ".O\n.O\n..\n");
+    }
+
+    #[test]
+    fn fill_rect_then_clear_part_of_it() {
+        let mut universe = empty(5, 4);
+        universe.fill_rect(1, 1, 2, 3, Cell::Alive);
+        assert_eq!(universe.to_plaintext(), ".....\n.OOO.\n.OOO.\n.....\n");
+        // corners in either order, clamped to the board
+        universe.fill_rect(9, 2, 2, 9, Cell::Dead);
+        assert_eq!(universe.to_plaintext(), ".....\n.OOO.\n.O...\n.....\n");
+        assert_eq!(universe.population(), 4);
+    }
+
+    #[test]
+    fn inverting_twice_is_the_identity() {
+        let original = Universe::new_random(10, 6, 0.3, 4);
+        let mut universe = original.clone();
+        universe.invert();
+        assert_eq!(universe.population(), 60 - original.population());
+        assert!(universe != original);
+        universe.invert();
+        assert!(universe == original);
+        assert_eq!(sorted(universe.live_cells()), sorted(original.live_cells()));
+    }
+
+    #[test]
+    fn births_take_their_parents_majority_color() {
+        const RED: u8 = 1;
+        const BLUE: u8 = 2;
+        for &(majority, minority) in &[(RED, BLUE), (BLUE, RED)] {
+            // (2, 1) has exactly three parents, and is the only birth
+            let mut universe =
+                Universe::from_plaintext(".....\nO.O..\n.....\n..O..\n.....").unwrap();
+            universe.set_boundary(Boundary::Dead);
+            universe.set_color(1, 0, majority);
+            universe.set_color(1, 2, majority);
+            universe.set_color(3, 2, minority);
+            universe.update();
+            assert_eq!(universe.last_births(), 1);
+            assert_eq!(universe.get_cell(2, 1), Cell::Alive);
+            assert_eq!(universe.color_at(2, 1), majority);
+        }
+    }
+
+    #[test]
+    fn uncolored_boards_stay_uncolored() {
+        let mut universe = Universe::new_random(30, 30, 0.4, 12);
+        for _ in 0..10 {
+            universe.update();
+        }
+        assert!(!universe.colored);
+        assert!(universe.colors.iter().all(|&color| color == 0));
+    }
+
+    #[test]
+    fn ages_count_generations_survived() {
+        let mut block = Universe::from_plaintext("....\n.OO.\n.OO.\n....").unwrap();
+        for generation in 1..=5 {
+            block.update();
+            assert_eq!(block.age_at(1, 1), generation);
+            assert_eq!(block.age_at(2, 2), generation);
+        }
+        assert_eq!(block.age_at(0, 0), 0);
+
+        // the blinker's middle survives while its ends are born anew
+        let mut blinker = Universe::from_plaintext(".....\n.....\n.OOO.\n.....\n.....").unwrap();
+        blinker.update();
+        blinker.update();
+        assert_eq!(blinker.age_at(2, 2), 2);
+        assert_eq!(blinker.age_at(2, 1), 0);
+        // and editing a cell starts it over
+        blinker.set_cell(2, 2, Cell::Dead);
+        blinker.set_cell(2, 2, Cell::Alive);
+        assert_eq!(blinker.age_at(2, 2), 0);
     }
 }
@@ -0,0 +1,473 @@
+use crate::rule::Rule;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+// Hashlife: the board is a quadtree of canonical (hash-consed) nodes, and the
+// result of advancing each node is memoized, so patterns with a lot of
+// repetition in space or time can be stepped billions of generations.
+//
+// A node at level k covers a 2^k x 2^k square. `successor` advances a node by
+// 2^j generations (j <= k - 2) and returns the centered 2^(k-1) square.
+
+type NodeId = u32;
+
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+
+// The biggest root: 2^62 cells across keeps every coordinate, and the origin
+// as it moves, inside an i64.
+const MAX_LEVEL: u8 = 62;
+
+// Returned by `step` when the pattern would outgrow `MAX_LEVEL`, or the
+// generation count a u64.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TooFar;
+
+impl fmt::Display for TooFar {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "too many generations for hashlife's coordinates")
+    }
+}
+
+impl Error for TooFar {}
+
+#[derive(Clone, Copy)]
+struct Node {
+    nw: NodeId,
+    ne: NodeId,
+    sw: NodeId,
+    se: NodeId,
+    level: u8,
+    population: u64,
+}
+
+pub struct HashlifeUniverse {
+    nodes: Vec<Node>,
+    canonical: HashMap<(NodeId, NodeId, NodeId, NodeId), NodeId>,
+    results: HashMap<(NodeId, u8), NodeId>,
+    empty: Vec<NodeId>,
+    root: NodeId,
+    // (col, row) of the top-left corner of the root node
+    origin: (i64, i64),
+    generation: u64,
+    rule: Rule,
+}
+
+impl HashlifeUniverse {
+    pub fn new() -> HashlifeUniverse {
+        let leaf = |population| Node {
+            nw: DEAD,
+            ne: DEAD,
+            sw: DEAD,
+            se: DEAD,
+            level: 0,
+            population,
+        };
+        let mut universe = HashlifeUniverse {
+            nodes: vec![leaf(0), leaf(1)],
+            canonical: HashMap::new(),
+            results: HashMap::new(),
+            empty: vec![DEAD],
+            root: DEAD,
+            origin: (-4, -4),
+            generation: 0,
+            rule: Rule::default(),
+        };
+        universe.root = universe.empty_node(3);
+        universe
+    }
+
+    // live cells given as (col, row), like `live_cells` hands them back
+    pub fn from_cells(cells: &[(i64, i64)]) -> HashlifeUniverse {
+        let mut universe = HashlifeUniverse::new();
+        for &(col, row) in cells {
+            universe.set_alive(row, col);
+        }
+        universe
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    // rules that give birth on 0 neighbors and Generations rules aren't
+    // supported
+    pub fn set_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+        self.results.clear();
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn population(&self) -> u64 {
+        self.nodes[self.root as usize].population
+    }
+
+    pub fn set_alive(&mut self, row: i64, col: i64) {
+        loop {
+            let size = 1i64 << self.level(self.root);
+            let (x, y) = (col - self.origin.0, row - self.origin.1);
+            if x >= 0 && y >= 0 && x < size && y < size {
+                self.root = self.set_alive_in(self.root, x, y);
+                return;
+            }
+            self.expand();
+        }
+    }
+
+    // live cells as (col, row)
+    pub fn live_cells(&self) -> impl Iterator<Item = (i64, i64)> {
+        let mut cells = Vec::with_capacity(self.population() as usize);
+        self.collect_live(self.root, self.origin.0, self.origin.1, &mut cells);
+        cells.into_iter()
+    }
+
+    // advances any number of generations, one power of two at a time; on an
+    // error the board is left at the last power of two it got through
+    pub fn step(&mut self, generations: u64) -> Result<(), TooFar> {
+        if self.generation.checked_add(generations).is_none() {
+            return Err(TooFar);
+        }
+        for j in 0..64 {
+            if (generations >> j) & 1 == 1 {
+                self.step_pow2(j)?;
+                self.generation += 1 << j;
+            }
+        }
+        Ok(())
+    }
+
+    fn step_pow2(&mut self, j: u8) -> Result<(), TooFar> {
+        // Grow until the pattern sits in the middle 2^(k-2) square and the
+        // root is big enough to advance 2^j generations; the pattern then
+        // can't escape the 2^(k-1) square the successor hands back.
+        loop {
+            let level = self.level(self.root);
+            let inner = self.centered(self.root);
+            let inner = self.centered(inner);
+            if level >= j + 3 && self.population_of(inner) == self.population() {
+                break;
+            }
+            if level >= MAX_LEVEL {
+                return Err(TooFar);
+            }
+            self.expand();
+        }
+
+        let quarter = 1i64 << (self.level(self.root) - 2);
+        self.root = self.successor(self.root, j);
+        self.origin = (self.origin.0 + quarter, self.origin.1 + quarter);
+        Ok(())
+    }
+
+    fn level(&self, node: NodeId) -> u8 {
+        self.nodes[node as usize].level
+    }
+
+    fn population_of(&self, node: NodeId) -> u64 {
+        self.nodes[node as usize].population
+    }
+
+    fn join(&mut self, nw: NodeId, ne: NodeId, sw: NodeId, se: NodeId) -> NodeId {
+        if let Some(&node) = self.canonical.get(&(nw, ne, sw, se)) {
+            return node;
+        }
+        let population = [nw, ne, sw, se]
+            .iter()
+            .map(|&child| self.population_of(child))
+            .sum();
+        let node = self.nodes.len() as NodeId;
+        self.nodes.push(Node {
+            nw,
+            ne,
+            sw,
+            se,
+            level: self.level(nw) + 1,
+            population,
+        });
+        self.canonical.insert((nw, ne, sw, se), node);
+        node
+    }
+
+    fn empty_node(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let child = *self.empty.last().unwrap();
+            let node = self.join(child, child, child, child);
+            self.empty.push(node);
+        }
+        self.empty[level as usize]
+    }
+
+    // doubles the root, keeping the existing cells in the middle
+    fn expand(&mut self) {
+        let root = self.nodes[self.root as usize];
+        let border = self.empty_node(root.level - 1);
+        let nw = self.join(border, border, border, root.nw);
+        let ne = self.join(border, border, root.ne, border);
+        let sw = self.join(border, root.sw, border, border);
+        let se = self.join(root.se, border, border, border);
+        self.root = self.join(nw, ne, sw, se);
+
+        let half = 1i64 << (root.level - 1);
+        self.origin = (self.origin.0 - half, self.origin.1 - half);
+    }
+
+    fn set_alive_in(&mut self, node: NodeId, x: i64, y: i64) -> NodeId {
+        let n = self.nodes[node as usize];
+        if n.level == 0 {
+            return ALIVE;
+        }
+        let half = 1i64 << (n.level - 1);
+        match (x < half, y < half) {
+            (true, true) => {
+                let nw = self.set_alive_in(n.nw, x, y);
+                self.join(nw, n.ne, n.sw, n.se)
+            }
+            (false, true) => {
+                let ne = self.set_alive_in(n.ne, x - half, y);
+                self.join(n.nw, ne, n.sw, n.se)
+            }
+            (true, false) => {
+                let sw = self.set_alive_in(n.sw, x, y - half);
+                self.join(n.nw, n.ne, sw, n.se)
+            }
+            (false, false) => {
+                let se = self.set_alive_in(n.se, x - half, y - half);
+                self.join(n.nw, n.ne, n.sw, se)
+            }
+        }
+    }
+
+    fn collect_live(&self, node: NodeId, col: i64, row: i64, cells: &mut Vec<(i64, i64)>) {
+        let n = self.nodes[node as usize];
+        if n.population == 0 {
+            return;
+        }
+        if n.level == 0 {
+            cells.push((col, row));
+            return;
+        }
+        let half = 1i64 << (n.level - 1);
+        self.collect_live(n.nw, col, row, cells);
+        self.collect_live(n.ne, col + half, row, cells);
+        self.collect_live(n.sw, col, row + half, cells);
+        self.collect_live(n.se, col + half, row + half, cells);
+    }
+
+    // the middle half of a node, one level down
+    fn centered(&mut self, node: NodeId) -> NodeId {
+        let n = self.nodes[node as usize];
+        let (nw, ne) = (self.nodes[n.nw as usize], self.nodes[n.ne as usize]);
+        let (sw, se) = (self.nodes[n.sw as usize], self.nodes[n.se as usize]);
+        self.join(nw.se, ne.sw, sw.ne, se.nw)
+    }
+
+    // the square straddling the seam between two side-by-side nodes
+    fn centered_horizontal(&mut self, west: NodeId, east: NodeId) -> NodeId {
+        let (w, e) = (self.nodes[west as usize], self.nodes[east as usize]);
+        self.join(w.ne, e.nw, w.se, e.sw)
+    }
+
+    // the square straddling the seam between two stacked nodes
+    fn centered_vertical(&mut self, north: NodeId, south: NodeId) -> NodeId {
+        let (n, s) = (self.nodes[north as usize], self.nodes[south as usize]);
+        self.join(n.sw, n.se, s.nw, s.ne)
+    }
+
+    fn successor(&mut self, node: NodeId, j: u8) -> NodeId {
+        if let Some(&result) = self.results.get(&(node, j)) {
+            return result;
+        }
+
+        let n = self.nodes[node as usize];
+        let result = if n.population == 0 {
+            self.empty_node(n.level - 1)
+        } else if n.level == 2 {
+            self.step_leaf_square(node)
+        } else {
+            // nine overlapping squares one level down
+            let n00 = n.nw;
+            let n01 = self.centered_horizontal(n.nw, n.ne);
+            let n02 = n.ne;
+            let n10 = self.centered_vertical(n.nw, n.sw);
+            let n11 = self.centered(node);
+            let n12 = self.centered_vertical(n.ne, n.se);
+            let n20 = n.sw;
+            let n21 = self.centered_horizontal(n.sw, n.se);
+            let n22 = n.se;
+            let nine = [n00, n01, n02, n10, n11, n12, n20, n21, n22];
+
+            // at full speed both halves of the step advance 2^(k-3)
+            // generations; slower steps just crop the first half
+            let mut r = [DEAD; 9];
+            for (i, &sub) in nine.iter().enumerate() {
+                r[i] = if j == n.level - 2 {
+                    self.successor(sub, j - 1)
+                } else {
+                    self.centered(sub)
+                };
+            }
+            let second_j = if j == n.level - 2 { j - 1 } else { j };
+
+            let nw = self.join(r[0], r[1], r[3], r[4]);
+            let ne = self.join(r[1], r[2], r[4], r[5]);
+            let sw = self.join(r[3], r[4], r[6], r[7]);
+            let se = self.join(r[4], r[5], r[7], r[8]);
+            let nw = self.successor(nw, second_j);
+            let ne = self.successor(ne, second_j);
+            let sw = self.successor(sw, second_j);
+            let se = self.successor(se, second_j);
+            self.join(nw, ne, sw, se)
+        };
+
+        self.results.insert((node, j), result);
+        result
+    }
+
+    // one generation of a 4x4 square, giving its middle 2x2
+    fn step_leaf_square(&mut self, node: NodeId) -> NodeId {
+        let mut grid = [[false; 4]; 4];
+        let n = self.nodes[node as usize];
+        for (quadrant, &(qx, qy)) in [n.nw, n.ne, n.sw, n.se]
+            .iter()
+            .zip([(0, 0), (2, 0), (0, 2), (2, 2)].iter())
+        {
+            let q = self.nodes[*quadrant as usize];
+            for (leaf, &(lx, ly)) in [q.nw, q.ne, q.sw, q.se]
+                .iter()
+                .zip([(0, 0), (1, 0), (0, 1), (1, 1)].iter())
+            {
+                grid[qy + ly][qx + lx] = *leaf == ALIVE;
+            }
+        }
+
+        let mut next = [DEAD; 4];
+        for (i, &(x, y)) in [(1, 1), (2, 1), (1, 2), (2, 2)].iter().enumerate() {
+            let block: usize = grid[y - 1..=y + 1]
+                .iter()
+                .map(|cells| cells[x - 1..=x + 1].iter().filter(|&&alive| alive).count())
+                .sum();
+            let live_neighbors = (block - grid[y][x] as usize) as u8;
+            let alive = if grid[y][x] {
+                self.rule.survives(live_neighbors)
+            } else {
+                self.rule.is_born(live_neighbors)
+            };
+            next[i] = if alive { ALIVE } else { DEAD };
+        }
+        self.join(next[0], next[1], next[2], next[3])
+    }
+}
+
+impl Default for HashlifeUniverse {
+    fn default() -> HashlifeUniverse {
+        HashlifeUniverse::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_of_life::{Boundary, Universe};
+    use crate::patterns;
+
+    fn sorted(cells: impl Iterator<Item = (i64, i64)>) -> Vec<(i64, i64)> {
+        let mut cells: Vec<(i64, i64)> = cells.collect();
+        cells.sort_unstable();
+        cells
+    }
+
+    fn glider() -> Vec<(i64, i64)> {
+        patterns::glider()
+            .into_iter()
+            .map(|(row, col)| (col as i64, row as i64))
+            .collect()
+    }
+
+    #[test]
+    fn glider_travels_a_million_cells() {
+        let mut hashlife = HashlifeUniverse::from_cells(&glider());
+        hashlife.step(4_000_000).unwrap();
+        assert_eq!(hashlife.generation(), 4_000_000);
+        assert_eq!(hashlife.population(), 5);
+        let moved = glider()
+            .into_iter()
+            .map(|(col, row)| (col + 1_000_000, row + 1_000_000));
+        assert_eq!(sorted(hashlife.live_cells()), sorted(moved));
+    }
+
+    #[test]
+    fn step_matches_repeated_updates() {
+        for &(rule, seed) in &[(Rule::conway(), 3), (Rule::highlife(), 4)] {
+            let soup = Universe::new_random(16, 16, 0.5, seed);
+            let mut dense = Universe::new_random(160, 160, 0.0, 0);
+            dense.set_rule(rule);
+            dense.set_boundary(Boundary::Dead);
+            let cells: Vec<(u32, u32)> = soup.live_cells().map(|(col, row)| (row, col)).collect();
+            dense.insert_pattern(&cells, 72, 72);
+            let cells: Vec<(i64, i64)> = dense
+                .live_cells()
+                .map(|(col, row)| (col as i64, row as i64))
+                .collect();
+            let mut hashlife = HashlifeUniverse::from_cells(&cells);
+            hashlife.set_rule(rule);
+
+            // uneven steps, so every power of two up to 32 gets used; 70
+            // generations in all can't carry anything to the dense board's
+            // edges
+            let mut generation = 0;
+            for &steps in &[1, 2, 3, 7, 12, 45] {
+                hashlife.step(steps).unwrap();
+                for _ in 0..steps {
+                    dense.update();
+                }
+                generation += steps;
+                let dense_cells = dense
+                    .live_cells()
+                    .map(|(col, row)| (col as i64, row as i64));
+                assert_eq!(
+                    sorted(hashlife.live_cells()),
+                    sorted(dense_cells),
+                    "{} gen {}",
+                    rule,
+                    generation
+                );
+                assert_eq!(hashlife.population(), dense.population() as u64);
+            }
+        }
+    }
+
+    #[test]
+    fn step_zero_changes_nothing() {
+        let mut hashlife = HashlifeUniverse::from_cells(&glider());
+        hashlife.step(0).unwrap();
+        assert_eq!(hashlife.generation(), 0);
+        assert_eq!(sorted(hashlife.live_cells()), sorted(glider().into_iter()));
+    }
+
+    #[test]
+    fn refuses_steps_past_its_coordinates() {
+        let mut hashlife = HashlifeUniverse::from_cells(&glider());
+        assert_eq!(hashlife.step(1 << 60), Err(TooFar));
+        assert_eq!(hashlife.step(u64::MAX), Err(TooFar));
+        // a still life never outgrows its root, but the generation count can
+        let mut block = HashlifeUniverse::from_cells(&[(0, 0), (1, 0), (0, 1), (1, 1)]);
+        assert_eq!(block.step(1 << 59), Ok(()));
+        assert_eq!(block.population(), 4);
+        assert_eq!(block.step(u64::MAX), Err(TooFar));
+        assert_eq!(block.generation(), 1 << 59);
+    }
+
+    #[test]
+    fn keeps_the_generations_it_got_through() {
+        // the glider is fine for the low bits but can't go 2^60 further
+        let mut hashlife = HashlifeUniverse::from_cells(&glider());
+        assert_eq!(hashlife.step((1 << 60) + 4), Err(TooFar));
+        assert_eq!(hashlife.generation(), 4);
+        let moved = glider().into_iter().map(|(col, row)| (col + 1, row + 1));
+        assert_eq!(sorted(hashlife.live_cells()), sorted(moved));
+    }
+}
@@ -0,0 +1,316 @@
+use crate::cli::Options;
+use crate::error::ParseError;
+use crate::game_of_life::{Boundary, Universe};
+use crate::hashlife::HashlifeUniverse;
+use crate::packed::PackedUniverse;
+use crate::rule::Rule;
+use crate::sparse::SparseUniverse;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::str::FromStr;
+use std::time::Instant;
+
+pub const STATS_CSV_HEADER: &str = "generation,population,births,deaths,density";
+
+/// Appends a `STATS_CSV_HEADER` row for the universe's current generation.
+///
+/// ```
+/// use life::game_of_life::Universe;
+/// use life::headless::{write_stats_row, STATS_CSV_HEADER};
+///
+/// let mut blinker = Universe::from_plaintext(".....\n.....\n.OOO.\n.....\n.....").unwrap();
+/// let mut csv = format!("{}\n", STATS_CSV_HEADER).into_bytes();
+/// for _ in 0..2 {
+///     write_stats_row(&mut csv, &blinker).unwrap();
+///     blinker.update();
+/// }
+/// write_stats_row(&mut csv, &blinker).unwrap();
+/// assert_eq!(
+///     String::from_utf8(csv).unwrap(),
+///     "generation,population,births,deaths,density\n\
+///      0,3,0,0,0.12\n\
+///      1,3,2,2,0.12\n\
+///      2,3,2,2,0.12\n"
+/// );
+/// ```
+pub fn write_stats_row<W: Write>(out: &mut W, universe: &Universe) -> io::Result<()> {
+    writeln!(
+        out,
+        "{},{},{},{},{}",
+        universe.generation(),
+        universe.population(),
+        universe.last_births(),
+        universe.last_deaths(),
+        universe.density()
+    )
+}
+
+// Runs the simulation with no window at all, for scripts and CI, and prints
+// where it ended up. With `dump_every` and `dump_file` set the board is also
+// written out in plaintext every that many generations, starting with the
+// first, and with `stats_csv` set every generation gets a row of statistics.
+pub fn run(mut universe: Universe, options: &Options) -> io::Result<()> {
+    let mut dump = match (&options.dump_file, options.dump_every) {
+        (Some(path), every) if every > 0 => Some((BufWriter::new(File::create(path)?), every)),
+        _ => None,
+    };
+
+    let mut stats = match &options.stats_csv {
+        Some(path) => {
+            let mut out = BufWriter::new(File::create(path)?);
+            writeln!(out, "{}", STATS_CSV_HEADER)?;
+            Some(out)
+        }
+        None => None,
+    };
+
+    for step in 0..=options.steps {
+        if let Some(out) = stats.as_mut() {
+            write_stats_row(out, &universe)?;
+        }
+        if let Some((out, every)) = dump.as_mut() {
+            if universe.generation() % *every == 0 {
+                writeln!(out, "# gen {}", universe.generation())?;
+                out.write_all(universe.to_plaintext().as_bytes())?;
+            }
+        }
+        if step < options.steps {
+            universe.update();
+        }
+    }
+    if let Some((mut out, _)) = dump {
+        out.flush()?;
+    }
+    if let Some(mut out) = stats {
+        out.flush()?;
+    }
+
+    println!(
+        "generation: {} population: {}",
+        universe.generation(),
+        universe.population()
+    );
+    Ok(())
+}
+
+// Which implementation a benchmark steps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Engine {
+    Sequential,
+    Parallel,
+    Packed,
+    Sparse,
+    Hashlife,
+}
+
+impl Engine {
+    pub fn name(self) -> &'static str {
+        match self {
+            Engine::Sequential => "sequential",
+            Engine::Parallel => "parallel",
+            Engine::Packed => "packed",
+            Engine::Sparse => "sparse",
+            Engine::Hashlife => "hashlife",
+        }
+    }
+}
+
+impl FromStr for Engine {
+    type Err = ParseError;
+
+    fn from_str(name: &str) -> Result<Engine, ParseError> {
+        match name {
+            "sequential" => Ok(Engine::Sequential),
+            "parallel" => Ok(Engine::Parallel),
+            "packed" => Ok(Engine::Packed),
+            "sparse" => Ok(Engine::Sparse),
+            "hashlife" => Ok(Engine::Hashlife),
+            _ => Err(ParseError::new(format!("unknown engine '{}'", name))),
+        }
+    }
+}
+
+// Times `steps` generations of `universe` on `engine` and prints one line,
+//
+//     <engine> <width> <height> <steps> <seconds> <generations per second>
+//
+// The sparse and Hashlife engines run on an unbounded plane rather than the
+// board's torus, so their boards drift apart from the others once anything
+// reaches an edge.
+pub fn bench(universe: Universe, steps: u64, engine: Engine) {
+    let (width, height) = (universe.width(), universe.height());
+    let start = Instant::now();
+    match engine {
+        Engine::Sequential => {
+            let mut universe = universe;
+            for _ in 0..steps {
+                universe.update();
+            }
+        }
+        Engine::Parallel => {
+            let mut universe = universe;
+            for _ in 0..steps {
+                universe.update_parallel();
+            }
+        }
+        Engine::Packed => {
+            let mut packed = PackedUniverse::from_universe(&universe);
+            for _ in 0..steps {
+                packed.update();
+            }
+        }
+        Engine::Sparse => {
+            let mut sparse = SparseUniverse::new();
+            sparse.set_rule(universe.rule());
+            let cells: Vec<(u32, u32)> =
+                universe.live_cells().map(|(col, row)| (row, col)).collect();
+            sparse.insert_pattern(&cells, 0, 0);
+            for _ in 0..steps {
+                sparse.update();
+            }
+        }
+        Engine::Hashlife => {
+            let cells: Vec<(i64, i64)> = universe
+                .live_cells()
+                .map(|(col, row)| (col as i64, row as i64))
+                .collect();
+            let mut hashlife = HashlifeUniverse::from_cells(&cells);
+            hashlife.set_rule(universe.rule());
+            if let Err(err) = hashlife.step(steps) {
+                eprintln!("{}", err);
+                return;
+            }
+        }
+    }
+    let seconds = start.elapsed().as_secs_f64();
+    let rate = if seconds > 0.0 {
+        steps as f64 / seconds
+    } else {
+        0.0
+    };
+    println!(
+        "{} {} {} {} {:.6} {:.1}",
+        engine.name(),
+        width,
+        height,
+        steps,
+        seconds,
+        rate
+    );
+}
+
+// What a random soup settled into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SoupOutcome {
+    // nothing left alive
+    Died,
+    // still lifes, with this many live cells
+    Still(usize),
+    // repeating every `period` generations, with this many live cells at the
+    // point it was noticed
+    Oscillating { period: usize, population: usize },
+    // still changing when the step cap ran out
+    Unsettled,
+}
+
+impl fmt::Display for SoupOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SoupOutcome::Died => write!(f, "died"),
+            SoupOutcome::Still(population) => write!(f, "still, population {}", population),
+            SoupOutcome::Oscillating { period, population } => {
+                write!(f, "p{}, population {}", period, population)
+            }
+            SoupOutcome::Unsettled => write!(f, "unsettled"),
+        }
+    }
+}
+
+// the soup sits in the middle of a board this many times its size, with
+// dead edges, so anything that escapes it dies at the edge rather than
+// wrapping around into the rest
+const SOUP_BOARD_SCALE: u32 = 4;
+// share of the soup's cells alive to start with
+const SOUP_DENSITY: f64 = 0.5;
+// characters in the longest bar of the soup histogram
+const SOUP_HISTOGRAM_WIDTH: u64 = 40;
+
+/// Runs a random `size` x `size` soup made from `seed` for up to `steps`
+/// generations, until it repeats a state.
+///
+/// ```
+/// use life::headless::{run_soup, SoupOutcome};
+/// use life::rule::Rule;
+///
+/// // two cells or fewer die out; three or four make a block
+/// let outcome = run_soup(2, 100, 7, Rule::conway());
+/// assert!(outcome == SoupOutcome::Died || outcome == SoupOutcome::Still(4));
+/// assert_eq!(run_soup(2, 100, 7, Rule::conway()), outcome);
+/// ```
+pub fn run_soup(size: u32, steps: u64, seed: u64, rule: Rule) -> SoupOutcome {
+    let board_size = size * SOUP_BOARD_SCALE;
+    let mut universe = Universe::with_rule(board_size, board_size, rule);
+    universe.set_boundary(Boundary::Dead);
+    universe.clear();
+    let soup = Universe::new_random(size, size, SOUP_DENSITY, seed);
+    let cells: Vec<(u32, u32)> = soup.live_cells().map(|(col, row)| (row, col)).collect();
+    let corner = (board_size - size) / 2;
+    universe.insert_pattern(&cells, corner, corner);
+
+    match universe.detect_period(steps as usize) {
+        None => SoupOutcome::Unsettled,
+        Some(_) if universe.population() == 0 => SoupOutcome::Died,
+        Some(_) if universe.is_stable() => SoupOutcome::Still(universe.population()),
+        Some(period) => SoupOutcome::Oscillating {
+            period,
+            population: universe.population(),
+        },
+    }
+}
+
+// Runs `soups` soups, from seeds counting up from `seed`, and prints how
+// many died, became still lifes, settled into oscillators of each period or
+// never settled, most common first, with the mean final population of each.
+pub fn soup_search(soups: u64, size: u32, steps: u64, seed: u64, rule: Rule) {
+    // (count, total population) for each outcome, by its period: 0 for
+    // dying out and usize::MAX for not settling
+    let mut outcomes: BTreeMap<usize, (u64, u64)> = BTreeMap::new();
+    for i in 0..soups {
+        let (period, population) = match run_soup(size, steps, seed.wrapping_add(i), rule) {
+            SoupOutcome::Died => (0, 0),
+            SoupOutcome::Still(population) => (1, population),
+            SoupOutcome::Oscillating { period, population } => (period, population),
+            SoupOutcome::Unsettled => (usize::MAX, 0),
+        };
+        let tally = outcomes.entry(period).or_insert((0, 0));
+        tally.0 += 1;
+        tally.1 += population as u64;
+    }
+
+    let mut outcomes: Vec<(usize, (u64, u64))> = outcomes.into_iter().collect();
+    outcomes.sort_by_key(|&(period, (count, _))| (std::cmp::Reverse(count), period));
+    let most = outcomes.first().map_or(1, |&(_, (count, _))| count);
+    println!(
+        "{} soups of {}x{} over at most {} generations",
+        soups, size, size, steps
+    );
+    for (period, (count, population)) in outcomes {
+        let bar = "#".repeat((count * SOUP_HISTOGRAM_WIDTH / most).max(1) as usize);
+        let mean = population as f64 / count as f64;
+        let outcome = match period {
+            0 => "died".to_string(),
+            1 => format!("still, mean population {:.1}", mean),
+            usize::MAX => "unsettled".to_string(),
+            period => format!("p{}, mean population {:.1}", period, mean),
+        };
+        println!(
+            "{:>8}  {:<width$}  {}",
+            count,
+            bar,
+            outcome,
+            width = SOUP_HISTOGRAM_WIDTH as usize
+        );
+    }
+}
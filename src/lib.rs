@@ -0,0 +1,28 @@
+// The simulation and everything around it that doesn't need a window: file
+// formats, the other engines, headless runs, rendering to images and the
+// terminal. The binary in main.rs adds the Piston viewer on top, and the web
+// build drives it through `web`.
+pub mod cli;
+pub mod color;
+#[cfg(feature = "config")]
+pub mod config;
+pub mod error;
+pub mod formats;
+pub mod game_of_life;
+pub mod hashlife;
+pub mod headless;
+pub mod packed;
+pub mod patterns;
+pub mod raster;
+pub mod recording;
+pub mod rng;
+pub mod rule;
+pub mod sparse;
+pub mod terminal;
+pub mod timing_buffer;
+pub mod undo;
+#[cfg(feature = "wasm")]
+pub mod web;
+
+pub use game_of_life::{Cell, Universe};
+pub use timing_buffer::TimingBuffer;
@@ -1,82 +1,1088 @@
+extern crate glutin;
 extern crate glutin_window;
 extern crate graphics;
 extern crate opengl_graphics;
 extern crate piston;
 
-mod game_of_life;
-mod timing_buffer;
-
+use glutin::window::Fullscreen;
 use glutin_window::GlutinWindow as Window;
-use opengl_graphics::{GlGraphics, GlyphCache, OpenGL, TextureSettings};
-use piston::event_loop::{EventSettings, Events};
-use piston::input::{RenderArgs, RenderEvent, UpdateArgs, UpdateEvent};
+use image::RgbaImage;
+use opengl_graphics::{Filter, GlGraphics, GlyphCache, OpenGL, Texture, TextureSettings};
+use piston::event_loop::{EventLoop, EventSettings, Events};
+use piston::input::{
+    Button, Key, MouseButton, MouseCursorEvent, MouseScrollEvent, PressEvent, ReleaseEvent,
+    RenderArgs, RenderEvent, UpdateArgs, UpdateEvent,
+};
 use piston::window::WindowSettings;
 use piston::Size;
-use game_of_life::{Universe};
-use timing_buffer::TimingBuffer;
+#[cfg(feature = "config")]
+use life::config;
+use life::color::{self, Color};
+use life::formats::Format;
+use life::game_of_life::{line_cells, Boundary};
+use life::recording::GifRecorder;
+use life::undo::UndoStack;
+use life::{cli, headless, raster, terminal};
+use life::{Cell, TimingBuffer, Universe};
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::Read;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-// Laptop size 2880 x 1800 (half is 1440x900)
-const WINDOW_SIZE: Size = Size {
-    width: 1440.0,
-    height: 900.0,
-};
+// What H shows over the board, as (keys, what they do).
+const HELP: &[(&str, &str)] = &[
+    ("Space", "pause/resume"),
+    ("N / .", "step one generation while paused"),
+    ("+ / -", "double/halve the simulation rate"),
+    ("click/drag", "toggle a cell, then paint that state"),
+    ("scroll", "zoom around the cursor"),
+    ("arrows/middle", "pan"),
+    ("Shift+drag", "select cells"),
+    ("Ctrl+C/V", "copy the selection/paste at the cursor"),
+    ("Ctrl+Z", "undo the last edit"),
+    ("B", "cycle the brush size"),
+    ("L", "line tool"),
+    ("Backspace", "reset to generation 0"),
+    ("Enter", "new random board"),
+    ("Z", "zoom to fit the live cells"),
+    ("M", "minimap of the whole board"),
+    ("P", "find oscillators and color them by period"),
+    ("G", "grid lines"),
+    ("W", "ghosts across the edges of a wrapping board"),
+    ("T / Shift+T", "show/clear the trail of every cell ever alive"),
+    ("C", "solid/age-shaded cells"),
+    ("O", "square/round cells"),
+    ("S", "screenshot"),
+    ("R", "start/stop recording a GIF"),
+    ("1-5", "HUD fields"),
+    ("U", "uncap/cap the frame rate"),
+    ("F", "fullscreen"),
+    ("H", "this help"),
+    ("Esc", "quit"),
+];
+
+// DejaVu Sans, so the HUD works whatever fonts are installed
+const BUNDLED_FONT: &[u8] = include_bytes!("../assets/fonts/DejaVuSans.ttf");
+
+// How live cells are filled in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorMode {
+    // everything in the foreground color
+    Solid,
+    // shaded by how long the cell has been alive, from `young_color` for a
+    // newborn to `old_color` for one that's survived `max_age` generations
+    Age,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CellShape {
+    Square,
+    // a circle inscribed in the cell's square
+    Circle,
+}
+
+// Which fields the HUD shows, as a bitset.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HudFlags(u8);
+
+impl HudFlags {
+    pub const FPS: HudFlags = HudFlags(1);
+    pub const UPS: HudFlags = HudFlags(1 << 1);
+    pub const GENERATION: HudFlags = HudFlags(1 << 2);
+    pub const POPULATION: HudFlags = HudFlags(1 << 3);
+    // the (row, col) under the mouse
+    pub const CURSOR: HudFlags = HudFlags(1 << 4);
+    pub const ALL: HudFlags = HudFlags(0b1_1111);
+    pub const NONE: HudFlags = HudFlags(0);
+
+    pub fn contains(self, flags: HudFlags) -> bool {
+        self.0 & flags.0 == flags.0
+    }
+
+    pub fn toggle(&mut self, flags: HudFlags) {
+        self.0 ^= flags.0;
+    }
+}
+
+impl std::ops::BitOr for HudFlags {
+    type Output = HudFlags;
+
+    fn bitor(self, other: HudFlags) -> HudFlags {
+        HudFlags(self.0 | other.0)
+    }
+}
+
+// What a background scan for oscillators found, for the generation it
+// started from.
+struct OscillatorScan {
+    generation: u64,
+    // row-major, from `Universe::oscillator_periods`
+    periods: Vec<u32>,
+    // the cycle length of the whole board, if it repeated within the scan
+    period: Option<usize>,
+}
+
+// The board at one pixel per cell, kept on the GPU and patched from the
+// universe's changed cells, so a frame costs one textured quad rather than a
+// rectangle per live cell.
+struct BoardTexture {
+    image: RgbaImage,
+    texture: Texture,
+    // the generation `image` shows
+    generation: u64,
+}
 
 pub struct ViewOfLife<'a> {
     gl: GlGraphics, // OpenGL drawing backend.
     glyph_cache: GlyphCache<'a>,
     fps: TimingBuffer,
+    hud: HudFlags,
     square: graphics::types::Rectangle,
     cell_size: f64,
     offset_x: f64,
     offset_y: f64,
+    // the window size in the coordinates we draw in, from the last render
+    view_size: [f64; 2],
+    // framebuffer pixels per window coordinate, 2.0 on a Retina display
+    pixel_ratio: f64,
+    // magnification on top of fitting the board to the window, 1.0 to fit
+    zoom: f64,
+    // how far zooming and panning have moved the board from its centered
+    // position
+    pan: [f64; 2],
+    // whether the middle button is held, dragging the board around
+    panning: bool,
+    show_grid: bool,
+    bg_color: Color,
+    fg_color: Color,
+    grid_color: Color,
+    color_mode: ColorMode,
+    young_color: Color,
+    old_color: Color,
+    max_age: u32,
+    cell_shape: CellShape,
+    // only used for solid square cells, the one look a texture can draw
+    board: Option<BoardTexture>,
+    // set by anything that changes the board behind the texture's back,
+    // such as editing, so the next frame repaints it from scratch
+    full_repaint: bool,
+    // the seed of the last random board and when it was made, so the HUD
+    // can show it for a while
+    last_seed: Option<(u64, Instant)>,
+    // the GIF each generation is being appended to, while recording
+    recorder: Option<GifRecorder>,
+    // pixels per cell in recordings
+    recording_scale: u32,
+    // hundredths of a second each generation is shown for in recordings
+    recording_delay: u16,
     universe: Universe,
+    // simulation steps per real second, independent of the frame rate
+    updates_per_second: f64,
+    // seconds of simulation time not yet stepped
+    update_accumulator: f64,
+    paused: bool,
+    // advance one generation on the next update, even while paused
+    step_once: bool,
+    // last known mouse position, in window coordinates
+    cursor: [f64; 2],
+    // while the left button is held, the state being painted onto every cell
+    // the cursor passes over
+    drawing: Option<Cell>,
+    // width of the square of cells each paint sets, centered on the cursor
+    brush_size: u32,
+    // while on, clicks draw straight lines instead of painting
+    line_tool: bool,
+    // the cell the line tool's first click picked, waiting for the second
+    line_start: Option<(u32, u32)>,
+    shift_held: bool,
+    // Ctrl, or Cmd on a Mac
+    ctrl_held: bool,
+    // (r0, c0, r1, c1) corners of the selected cells, inclusive; r0, c0 is
+    // where the shift-drag started
+    selection: Option<(u32, u32, u32, u32)>,
+    // whether a shift-drag is moving the selection's second corner
+    selecting: bool,
+    // the last copied selection, pasted with its top-left at the cursor
+    clipboard: Option<Universe>,
+    // edits made with the mouse and by pasting, for Ctrl+Z
+    undo: UndoStack,
+    show_help: bool,
+    // on a torus, whether to draw faded copies of the cells near each edge
+    // past the opposite one, so patterns crossing the seam stay in one piece
+    show_ghosts: bool,
+    // the most frames a second the event loop draws
+    max_fps: u64,
+    // whether to ignore `max_fps` and draw as fast as possible
+    uncapped: bool,
+    // frames a cell takes to fade out after it dies, 0 for none
+    fade_frames: u32,
+    // frames left for each fading cell, by (col, row)
+    fading: HashMap<(u32, u32), u32>,
+    show_trail: bool,
+    show_minimap: bool,
+    // whether to look for oscillators and tint them by period
+    show_oscillators: bool,
+    // the last finished scan, and the one running on another thread
+    oscillators: Option<OscillatorScan>,
+    scanning: Option<Receiver<OscillatorScan>>,
+    // row-major, whether each cell has been alive since the trail was last
+    // cleared
+    visited: Vec<bool>,
 }
 
 impl ViewOfLife<'_> {
-    const BG_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
-    const FG_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+    // below this many pixels per cell the grid would drown out the board
+    const MIN_GRID_CELL_SIZE: f64 = 4.0;
+
+    // space above the board for the HUD
+    const TOP_MARGIN: f64 = 30.0;
+
+    const MIN_ZOOM: f64 = 0.25;
+    const MAX_ZOOM: f64 = 64.0;
+    // how far one arrow key press pans the board
+    const PAN_STEP: f64 = 40.0;
+    // how much of the board has to stay on screen however far it's panned
+    const PAN_MARGIN: f64 = 40.0;
+    // pixels per cell in screenshots
+    const SCREENSHOT_SCALE: u32 = 4;
+    const RECORDING_PATH: &'static str = "recording.gif";
+    const DEFAULT_RECORDING_SCALE: u32 = 2;
+    const DEFAULT_RECORDING_DELAY: u16 = 5;
+    // the age shading's gradient, from newborn to `DEFAULT_MAX_AGE`
+    // generations old
+    const DEFAULT_YOUNG_COLOR: Color = [1.0, 0.9, 0.2, 1.0];
+    const DEFAULT_OLD_COLOR: Color = [0.6, 0.0, 0.3, 1.0];
+    const DEFAULT_MAX_AGE: u32 = 50;
+    // share of cells alive on a freshly randomized board
+    const RANDOM_DENSITY: f64 = 0.3;
+    // how long a new seed stays in the HUD
+    const SEED_DISPLAY_TIME: Duration = Duration::from_secs(5);
+    // edits Ctrl+Z can go back through
+    const UNDO_DEPTH: usize = 100;
+    // the brush sizes B cycles through
+    const BRUSH_SIZES: [u32; 3] = [1, 3, 5];
+    const HELP_BACKGROUND: Color = [0.0, 0.0, 0.0, 0.75];
+    const HELP_COLOR: Color = [1.0, 1.0, 1.0, 1.0];
+    const HELP_FONT_SIZE: u32 = 16;
+    const HELP_LINE_HEIGHT: f64 = 22.0;
+    // where the second column of the help starts
+    const HELP_ACTION_X: f64 = 160.0;
+    // how many cells in from each edge get a ghost past the opposite one
+    const GHOST_MARGIN: u32 = 4;
+    // ghosts are drawn in their cell's color at this much of its opacity
+    const GHOST_ALPHA: f32 = 0.35;
+    // the minimap's longer side, in window coordinates, and its distance from
+    // the window's bottom-right corner
+    const MINIMAP_SIZE: f64 = 160.0;
+    const MINIMAP_MARGIN: f64 = 10.0;
+    // window coordinates per sample of the board in the minimap
+    const MINIMAP_SAMPLE_SIZE: f64 = 2.0;
+    // the longest period an oscillator scan looks for
+    const MAX_OSCILLATOR_PERIOD: usize = 16;
+    // generations between oscillator scans
+    const OSCILLATOR_SCAN_INTERVAL: u64 = 10;
+    // tints for oscillators of period 2, 3, 4 and anything longer
+    const OSCILLATOR_COLORS: [Color; 4] = [
+        [0.1, 0.4, 0.9, 1.0],
+        [0.1, 0.7, 0.2, 1.0],
+        [0.9, 0.5, 0.0, 1.0],
+        [0.7, 0.1, 0.7, 1.0],
+    ];
+    // how far the trail's color is from the background towards the cells'
+    const TRAIL_TINT: f32 = 0.15;
+    const SELECTION_COLOR: Color = [0.2, 0.4, 1.0, 1.0];
+    const SELECTION_FILL: Color = [0.2, 0.4, 1.0, 0.2];
 
     // updates square, offset, cell_width for current screen size
     // we want the universe to be displayed with square boxes
+    // Drawing happens in window coordinates, which on a high-DPI display are
+    // smaller than the framebuffer's pixels, so the layout works in those.
     fn calculate (&mut self, args: &RenderArgs) {
-        let u_width = self.universe.width as f64;
-        let u_height = self.universe.height as f64;
-        let w_width = args.draw_size[0] as f64 / 2.0;
-        let w_height = args.draw_size[1] as f64 / 2.0;
-        let top_margin = 30.0; // Space for FPS message
+        self.view_size = args.window_size;
+        self.pixel_ratio = device_pixel_ratio(args.window_size, args.draw_size);
+        self.layout();
+    }
+
+    fn layout(&mut self) {
+        let u_width = self.universe.width() as f64;
+        let u_height = self.universe.height() as f64;
+        let [w_width, w_height] = self.view_size;
+        let top_margin = Self::TOP_MARGIN;
         let cell_width = w_width / u_width;
         let cell_height = (w_height - top_margin) / u_height;
-        self.cell_size = cell_width.min(cell_height);
-        self.offset_x = (w_width - (self.cell_size * u_width)) / 2.0; // left margin
-        self.offset_y = (w_height - (self.cell_size * u_height)) / 2.0 + top_margin; // top margin
+        self.cell_size = cell_width.min(cell_height) * self.zoom;
+        self.offset_x = (w_width - (self.cell_size * u_width)) / 2.0 + self.pan[0]; // left margin
+        self.offset_y = (w_height - (self.cell_size * u_height)) / 2.0 + top_margin + self.pan[1]; // top margin
+
+        // keep at least a sliver of the board on screen
+        let x = self.offset_x.clamp(
+            (Self::PAN_MARGIN - self.cell_size * u_width).min(0.0),
+            (w_width - Self::PAN_MARGIN).max(0.0),
+        );
+        let y = self.offset_y.clamp(
+            (Self::PAN_MARGIN - self.cell_size * u_height).min(top_margin),
+            (w_height - Self::PAN_MARGIN).max(top_margin),
+        );
+        self.pan[0] += x - self.offset_x;
+        self.pan[1] += y - self.offset_y;
+        self.offset_x = x;
+        self.offset_y = y;
         self.square = graphics::rectangle::square(0.0, 0.0, self.cell_size);
     }
 
+    // zooms by `factor`, keeping the point under the cursor where it is
+    fn zoom_by(&mut self, factor: f64) {
+        let zoom = (self.zoom * factor).clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+        let ratio = zoom / self.zoom;
+        let (old_x, old_y) = (self.offset_x, self.offset_y);
+        self.zoom = zoom;
+        self.layout();
+        // where the board's corner has to land for the cursor to stay put,
+        // less where the zoomed board would sit on its own
+        self.pan[0] += self.cursor[0] + (old_x - self.cursor[0]) * ratio - self.offset_x;
+        self.pan[1] += self.cursor[1] + (old_y - self.cursor[1]) * ratio - self.offset_y;
+        self.layout();
+    }
+
+    // zooms and pans so the live cells fill the window, or back to the whole
+    // board when there aren't any
+    fn zoom_to_fit(&mut self) {
+        self.zoom = 1.0;
+        self.pan = [0.0, 0.0];
+        self.layout();
+        let (min_row, min_col, max_row, max_col) = match self.universe.bounding_box() {
+            Some(bounds) => bounds,
+            None => return,
+        };
+
+        let [w_width, w_height] = self.view_size;
+        let w_height = w_height - Self::TOP_MARGIN;
+        let (cols, rows) = ((max_col - min_col + 1) as f64, (max_row - min_row + 1) as f64);
+        let fit = (w_width / cols).min(w_height / rows);
+        self.zoom = (fit / self.cell_size).clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+        self.layout();
+
+        // move the middle of the live cells to the middle of the window
+        let center_x = self.offset_x + (min_col as f64 + cols / 2.0) * self.cell_size;
+        let center_y = self.offset_y + (min_row as f64 + rows / 2.0) * self.cell_size;
+        self.pan = [
+            w_width / 2.0 - center_x,
+            Self::TOP_MARGIN + w_height / 2.0 - center_y,
+        ];
+        self.layout();
+    }
+
+    // the (row, col) under a point in window coordinates, if it's on the board
+    fn cell_at(&self, position: [f64; 2]) -> Option<(u32, u32)> {
+        let col = ((position[0] - self.offset_x) / self.cell_size).floor();
+        let row = ((position[1] - self.offset_y) / self.cell_size).floor();
+        if col < 0.0
+            || row < 0.0
+            || col >= self.universe.width() as f64
+            || row >= self.universe.height() as f64
+        {
+            return None;
+        }
+        Some((row as u32, col as u32))
+    }
+
+    // sets every cell on the line between two points, so a fast drag doesn't
+    // leave gaps between mouse samples
+    fn paint_line(&mut self, from: [f64; 2], to: [f64; 2], state: Cell) {
+        let steps = ((to[0] - from[0]).abs().max((to[1] - from[1]).abs()) / self.cell_size)
+            .ceil()
+            .max(1.0) as u32;
+        for i in 0..=steps {
+            let t = i as f64 / steps as f64;
+            let point = [
+                from[0] + (to[0] - from[0]) * t,
+                from[1] + (to[1] - from[1]) * t,
+            ];
+            if let Some((row, col)) = self.cell_at(point) {
+                self.paint(row, col, state);
+            }
+        }
+    }
+
+    // sets the brush's square of cells around (row, col), clipped to the
+    // board
+    fn paint(&mut self, row: u32, col: u32, state: Cell) {
+        let half = self.brush_size / 2;
+        let bottom = (row + half).min(self.universe.height() - 1);
+        let right = (col + half).min(self.universe.width() - 1);
+        for row in row.saturating_sub(half)..=bottom {
+            for col in col.saturating_sub(half)..=right {
+                self.undo.set_cell(&mut self.universe, row, col, state);
+            }
+        }
+        self.full_repaint = true;
+    }
+
+    fn cycle_brush_size(&mut self) {
+        let sizes = Self::BRUSH_SIZES;
+        let next = sizes.iter().position(|&size| size == self.brush_size).map_or(0, |i| i + 1);
+        self.brush_size = sizes[next % sizes.len()];
+    }
+
+    // [x1, y1, x2, y2] of the grid lines between cells, clipped to the window
+    fn grid_lines(&self) -> Vec<[f64; 4]> {
+        let cell_size = self.cell_size;
+        if cell_size < Self::MIN_GRID_CELL_SIZE {
+            return Vec::new();
+        }
+        let [w_width, w_height] = self.view_size;
+        let visible = |offset: f64, extent: f64, cells: u32| {
+            let first = (-offset / cell_size).floor().max(0.0) as u32;
+            let last = ((extent - offset) / cell_size).ceil().min(cells as f64) as u32;
+            (first, last.max(first))
+        };
+        let (first_col, last_col) = visible(self.offset_x, w_width, self.universe.width());
+        let (first_row, last_row) = visible(self.offset_y, w_height, self.universe.height());
+        let (top, bottom) = (
+            self.offset_y + first_row as f64 * cell_size,
+            self.offset_y + last_row as f64 * cell_size,
+        );
+        let (left, right) = (
+            self.offset_x + first_col as f64 * cell_size,
+            self.offset_x + last_col as f64 * cell_size,
+        );
+
+        let mut lines = Vec::new();
+        for col in first_col..=last_col {
+            let x = self.offset_x + col as f64 * cell_size;
+            lines.push([x, top, x, bottom]);
+        }
+        for row in first_row..=last_row {
+            let y = self.offset_y + row as f64 * cell_size;
+            lines.push([left, y, right, y]);
+        }
+        lines
+    }
+
+    pub fn set_bg_color(&mut self, color: Color) {
+        self.bg_color = color;
+        self.full_repaint = true;
+    }
+
+    pub fn set_fg_color(&mut self, color: Color) {
+        self.fg_color = color;
+        self.full_repaint = true;
+    }
+
+    pub fn set_grid_color(&mut self, color: Color) {
+        self.grid_color = color;
+        self.full_repaint = true;
+    }
+
+    pub fn set_color_mode(&mut self, color_mode: ColorMode) {
+        self.color_mode = color_mode;
+    }
+
+    pub fn set_cell_shape(&mut self, cell_shape: CellShape) {
+        self.cell_shape = cell_shape;
+    }
+
+    // the ends of the gradient used by `ColorMode::Age`
+    pub fn set_age_gradient(&mut self, young_color: Color, old_color: Color, max_age: u32) {
+        self.young_color = young_color;
+        self.old_color = old_color;
+        self.max_age = max_age.max(1);
+    }
+
+    fn pan_by(&mut self, dx: f64, dy: f64) {
+        self.pan[0] += dx;
+        self.pan[1] += dy;
+        self.layout();
+    }
+
+    // the color a live cell at (row, col) is drawn in
+    fn fill_color(&self, row: u32, col: u32) -> Color {
+        match self.color_mode {
+            ColorMode::Solid => self.fg_color,
+            ColorMode::Age => {
+                let age = self.universe.age_at(row, col) as f32;
+                color::lerp(self.young_color, self.old_color, age / self.max_age as f32)
+            }
+        }
+    }
+
+    // writes the board to a PNG at `scale` pixels per cell, in the current
+    // colors but without the HUD, grid, zoom or pan
+    pub fn save_png<P: AsRef<Path>>(&self, path: P, scale: u32) -> image::ImageResult<()> {
+        raster::render(&self.universe, scale, self.bg_color, |row, col| {
+            self.fill_color(row, col)
+        })
+        .save(path)
+    }
+
+    fn screenshot(&self) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let path = format!("screenshot-{}.png", timestamp);
+        match self.save_png(&path, Self::SCREENSHOT_SCALE) {
+            Ok(()) => println!("saved {}", path),
+            Err(err) => eprintln!("couldn't save {}: {}", path, err),
+        }
+    }
+
+    pub fn set_recording_options(&mut self, scale: u32, delay: u16) {
+        self.recording_scale = scale.max(1);
+        self.recording_delay = delay;
+    }
+
+    fn record_frame(&mut self) {
+        let scale = self.recording_scale;
+        let frame = raster::render(&self.universe, scale, self.bg_color, |row, col| {
+            self.fill_color(row, col)
+        });
+        if let Some(recorder) = self.recorder.as_mut() {
+            if let Err(err) = recorder.add_frame(&frame) {
+                eprintln!("recording stopped: {}", err);
+                self.recorder = None;
+            }
+        }
+    }
+
+    fn toggle_recording(&mut self) {
+        if self.recorder.take().is_some() {
+            println!("saved {}", Self::RECORDING_PATH);
+            return;
+        }
+        let scale = self.recording_scale;
+        match GifRecorder::create(
+            Self::RECORDING_PATH,
+            self.universe.width().saturating_mul(scale),
+            self.universe.height().saturating_mul(scale),
+            self.recording_delay,
+        ) {
+            Ok(recorder) => {
+                self.recorder = Some(recorder);
+                self.record_frame();
+            }
+            Err(err) => eprintln!("couldn't record {}: {}", Self::RECORDING_PATH, err),
+        }
+    }
+
+    // advances one generation, adding it to the recording if there is one
+    fn step(&mut self) {
+        self.universe.update();
+        if self.fade_frames > 0 {
+            for &(col, row) in self.universe.changed_cells() {
+                if self.universe.get_cell(row, col) != Cell::Alive {
+                    self.fading.insert((col, row), self.fade_frames);
+                }
+            }
+        }
+        if self.show_trail {
+            self.mark_visited();
+        }
+        if self.recorder.is_some() {
+            self.record_frame();
+        }
+    }
+
+    // adds the live cells to the trail, starting it over if the board has
+    // changed size
+    fn mark_visited(&mut self) {
+        let width = self.universe.width() as usize;
+        let area = width * self.universe.height() as usize;
+        if self.visited.len() != area {
+            self.visited = vec![false; area];
+        }
+        for (col, row) in self.universe.live_cells() {
+            self.visited[row as usize * width + col as usize] = true;
+        }
+    }
+
+    fn toggle_trail(&mut self) {
+        self.show_trail = !self.show_trail;
+        if self.show_trail {
+            self.mark_visited();
+        }
+    }
+
+    fn clear_trail(&mut self) {
+        self.visited.clear();
+        if self.show_trail {
+            self.mark_visited();
+        }
+    }
+
+    // picks up a finished oscillator scan, and starts another once the
+    // board has moved on far enough from the last one
+    fn poll_oscillators(&mut self) {
+        if !self.show_oscillators {
+            return;
+        }
+        if let Some(scanning) = &self.scanning {
+            match scanning.try_recv() {
+                Ok(scan) => {
+                    self.oscillators = Some(scan);
+                    self.scanning = None;
+                }
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => self.scanning = None,
+            }
+        }
+
+        let generation = self.universe.generation();
+        let due = match &self.oscillators {
+            Some(scan) => {
+                generation >= scan.generation + Self::OSCILLATOR_SCAN_INTERVAL
+                    || generation < scan.generation
+            }
+            None => true,
+        };
+        if due {
+            let universe = self.universe.clone();
+            let (sender, receiver) = mpsc::channel();
+            thread::spawn(move || {
+                let periods = universe.oscillator_periods(Self::MAX_OSCILLATOR_PERIOD);
+                let period = universe.clone().detect_period(2 * Self::MAX_OSCILLATOR_PERIOD);
+                let _ = sender.send(OscillatorScan {
+                    generation: universe.generation(),
+                    periods,
+                    period,
+                });
+            });
+            self.scanning = Some(receiver);
+        }
+    }
+
+    fn toggle_oscillators(&mut self) {
+        self.show_oscillators = !self.show_oscillators;
+        self.oscillators = None;
+        self.scanning = None;
+    }
+
+    // (x, y, color) of the live cells in oscillators, from the last scan
+    fn oscillator_cells(&self) -> Vec<(u32, u32, Color)> {
+        let scan = match &self.oscillators {
+            Some(scan) => scan,
+            None => return Vec::new(),
+        };
+        let width = self.universe.width() as usize;
+        if scan.periods.len() != width * self.universe.height() as usize {
+            return Vec::new();
+        }
+        self.universe
+            .live_cells()
+            .filter_map(|(col, row)| {
+                let period = scan.periods[row as usize * width + col as usize] as usize;
+                if period < 2 {
+                    return None;
+                }
+                let colors = &Self::OSCILLATOR_COLORS;
+                Some((col, row, colors[(period - 2).min(colors.len() - 1)]))
+            })
+            .collect()
+    }
+
+    // (x, y) of the dead cells on the trail
+    fn trail_cells(&self) -> Vec<(u32, u32)> {
+        let width = self.universe.width().max(1) as usize;
+        self.visited
+            .iter()
+            .enumerate()
+            .filter(|&(_, &visited)| visited)
+            .map(|(i, _)| ((i % width) as u32, (i / width) as u32))
+            .filter(|&(col, row)| self.universe.get_cell(row, col) != Cell::Alive)
+            .collect()
+    }
+
+    pub fn set_fade_frames(&mut self, fade_frames: u32) {
+        self.fade_frames = fade_frames;
+        self.fading.clear();
+    }
+
+    // (x, y, color) of the cells that died in the last `fade_frames`
+    // frames, more transparent the longer they've been dead, counting this
+    // frame against them
+    fn fading_cells(&mut self) -> Vec<(u32, u32, Color)> {
+        let mut cells = Vec::with_capacity(self.fading.len());
+        let fade_frames = self.fade_frames as f32;
+        let universe = &self.universe;
+        self.fading.retain(|&(col, row), frames_left| {
+            // reborn cells draw as live ones
+            if universe.get_cell(row, col) == Cell::Alive {
+                return false;
+            }
+            cells.push((col, row, *frames_left as f32 / (fade_frames + 1.0)));
+            *frames_left -= 1;
+            *frames_left > 0
+        });
+        cells
+            .into_iter()
+            .map(|(col, row, opacity)| {
+                let mut fill = self.fill_color(row, col);
+                fill[3] *= opacity;
+                (col, row, fill)
+            })
+            .collect()
+    }
+
+    fn hud_message(&self) -> String {
+        let mut fields = Vec::new();
+        if self.hud.contains(HudFlags::FPS) {
+            if self.uncapped {
+                fields.push(format!("{} uncapped", self.fps));
+            } else {
+                fields.push(format!("{} cap: {}", self.fps, self.max_fps));
+            }
+        }
+        if self.hud.contains(HudFlags::UPS) {
+            fields.push(format!("ups: {}", self.updates_per_second));
+        }
+        if self.hud.contains(HudFlags::GENERATION) {
+            fields.push(format!("gen: {}", self.universe.generation()));
+        }
+        if self.hud.contains(HudFlags::POPULATION) {
+            fields.push(format!("pop: {}", self.universe.population()));
+        }
+        if self.hud.contains(HudFlags::CURSOR) {
+            match self.cell_at(self.cursor) {
+                Some((row, col)) => fields.push(format!("cell: ({}, {})", row, col)),
+                None => fields.push("cell: --".to_string()),
+            }
+        }
+        if self.show_oscillators {
+            fields.push(match self.oscillators.as_ref().map(|scan| scan.period) {
+                None => "scanning".to_string(),
+                Some(Some(1)) => "stable".to_string(),
+                Some(Some(period)) => format!("oscillating p={}", period),
+                Some(None) => "chaotic".to_string(),
+            });
+        }
+        if let Some((seed, created)) = self.last_seed {
+            if created.elapsed() < Self::SEED_DISPLAY_TIME {
+                fields.push(format!("seed: {}", seed));
+            }
+        }
+        fields.join("   ")
+    }
+
+    // brings the board texture up to date, repainting only the changed cells
+    // when it's just one generation or a few edits behind
+    fn refresh_board(&mut self) {
+        let (width, height) = (self.universe.width(), self.universe.height());
+        let generation = self.universe.generation();
+        let fg = raster::to_rgba(self.fg_color);
+        let bg = raster::to_rgba(self.bg_color);
+
+        let incremental = !self.full_repaint
+            && self.board.as_ref().is_some_and(|board| {
+                board.image.dimensions() == (width, height)
+                    && (board.generation == generation || board.generation + 1 == generation)
+            });
+        if incremental {
+            let board = self.board.as_mut().unwrap();
+            for &(col, row) in self.universe.changed_cells() {
+                let alive = self.universe.get_cell(row, col) == Cell::Alive;
+                board.image.put_pixel(col, row, if alive { fg } else { bg });
+            }
+            board.texture.update(&board.image);
+            board.generation = generation;
+        } else {
+            let image = raster::render(&self.universe, 1, self.bg_color, |_, _| self.fg_color);
+            let settings = TextureSettings::new().filter(Filter::Nearest);
+            let texture = Texture::from_image(&image, &settings);
+            self.board = Some(BoardTexture {
+                image,
+                texture,
+                generation,
+            });
+            self.full_repaint = false;
+        }
+        self.universe.clear_changed_cells();
+    }
+
+    // replaces the board with a random soup under the same settings
+    pub fn randomize(&mut self, seed: u64) {
+        let mut universe = Universe::new_random(
+            self.universe.width(),
+            self.universe.height(),
+            Self::RANDOM_DENSITY,
+            seed,
+        );
+        universe.set_rule(self.universe.rule());
+        universe.set_boundary(self.universe.boundary());
+        universe.set_neighborhood(self.universe.neighborhood());
+        self.universe = universe;
+        // edits to the old board mean nothing on this one
+        self.undo.clear();
+        self.last_seed = Some((seed, Instant::now()));
+        self.full_repaint = true;
+    }
+
+    fn copy_selection(&mut self) {
+        if let Some((r0, c0, r1, c1)) = self.selection {
+            self.clipboard = Some(self.universe.subgrid(r0, c0, r1, c1));
+        }
+    }
+
+    // stamps the clipboard's live cells with its top-left corner under the
+    // cursor, dropping whatever would hang off the board rather than
+    // wrapping it around
+    fn paste(&mut self) {
+        let (clipboard, (row, col)) = match (&self.clipboard, self.cell_at(self.cursor)) {
+            (Some(clipboard), Some(cell)) => (clipboard, cell),
+            _ => return,
+        };
+        let (fits_rows, fits_cols) = (self.universe.height() - row, self.universe.width() - col);
+        let cells: Vec<(u32, u32)> = clipboard
+            .live_cells()
+            .filter(|&(x, y)| y < fits_rows && x < fits_cols)
+            .map(|(x, y)| (y, x))
+            .collect();
+        self.undo.begin();
+        for &(y, x) in &cells {
+            self.undo.record(&self.universe, row + y, col + x);
+        }
+        self.undo.end();
+        self.universe.insert_pattern(&cells, row, col);
+        self.full_repaint = true;
+    }
+
+    // (x, y, color) of the ghosts of the live cells within GHOST_MARGIN of an
+    // edge, in cells from the board's corner, so they lie off the board
+    fn ghost_cells(&self) -> Vec<(f64, f64, Color)> {
+        let (width, height) = (self.universe.width(), self.universe.height());
+        let margin = Self::GHOST_MARGIN;
+        // where a coordinate near an edge wraps to: 0.0 for not wrapping
+        let wraps = |at: u32, size: u32| {
+            let mut shifts = vec![0.0];
+            if at < margin {
+                shifts.push(size as f64);
+            }
+            if at + margin >= size {
+                shifts.push(-(size as f64));
+            }
+            shifts
+        };
+
+        let mut ghosts = Vec::new();
+        for (col, row) in self.universe.live_cells() {
+            if col >= margin && col + margin < width && row >= margin && row + margin < height {
+                continue;
+            }
+            let mut fill = self.fill_color(row, col);
+            fill[3] *= Self::GHOST_ALPHA;
+            for &dx in &wraps(col, width) {
+                for &dy in &wraps(row, height) {
+                    if dx != 0.0 || dy != 0.0 {
+                        ghosts.push((col as f64 + dx, row as f64 + dy, fill));
+                    }
+                }
+            }
+        }
+        ghosts
+    }
+
+    // The whole board shrunk into the bottom-right corner: its outline, a
+    // square for each sample with anything alive in it, and the part of the
+    // board in the window, all as [x, y, width, height] in window
+    // coordinates.
+    fn minimap(&self) -> ([f64; 4], Vec<[f64; 4]>, [f64; 4]) {
+        let (width, height) = (self.universe.width(), self.universe.height());
+        let scale = Self::MINIMAP_SIZE / width.max(height).max(1) as f64;
+        let (map_width, map_height) = (width as f64 * scale, height as f64 * scale);
+        let [w_width, w_height] = self.view_size;
+        let left = w_width - Self::MINIMAP_MARGIN - map_width;
+        let top = w_height - Self::MINIMAP_MARGIN - map_height;
+
+        // each sample covers a block of cells, and is drawn if any of them
+        // is alive
+        let sample = Self::MINIMAP_SAMPLE_SIZE;
+        let cols = ((map_width / sample).ceil() as u32).clamp(1, width.max(1));
+        let rows = ((map_height / sample).ceil() as u32).clamp(1, height.max(1));
+        let mut occupied = vec![false; (cols * rows) as usize];
+        for (col, row) in self.universe.live_cells() {
+            let (x, y) = (col * cols / width, row * rows / height);
+            occupied[(y * cols + x) as usize] = true;
+        }
+        let (sample_width, sample_height) = (map_width / cols as f64, map_height / rows as f64);
+        let samples = occupied
+            .iter()
+            .enumerate()
+            .filter(|&(_, &alive)| alive)
+            .map(|(i, _)| {
+                let (x, y) = (i as u32 % cols, i as u32 / cols);
+                [
+                    left + x as f64 * sample_width,
+                    top + y as f64 * sample_height,
+                    sample_width,
+                    sample_height,
+                ]
+            })
+            .collect();
+
+        // the window's corners in cells, clamped to the board
+        let to_cells = |position: f64, offset: f64, cells: u32| {
+            ((position - offset) / self.cell_size).clamp(0.0, cells as f64)
+        };
+        let (first_col, last_col) = (
+            to_cells(0.0, self.offset_x, width),
+            to_cells(w_width, self.offset_x, width),
+        );
+        let (first_row, last_row) = (
+            to_cells(0.0, self.offset_y, height),
+            to_cells(w_height, self.offset_y, height),
+        );
+        let viewport = [
+            left + first_col * scale,
+            top + first_row * scale,
+            (last_col - first_col) * scale,
+            (last_row - first_row) * scale,
+        ];
+        ([left, top, map_width, map_height], samples, viewport)
+    }
+
+    // the selection in window coordinates, as [x, y, width, height]
+    fn selection_rect(&self) -> Option<[f64; 4]> {
+        let (r0, c0, r1, c1) = self.selection?;
+        let (top, bottom) = (r0.min(r1), r0.max(r1));
+        let (left, right) = (c0.min(c1), c0.max(c1));
+        Some([
+            self.offset_x + left as f64 * self.cell_size,
+            self.offset_y + top as f64 * self.cell_size,
+            (right - left + 1) as f64 * self.cell_size,
+            (bottom - top + 1) as f64 * self.cell_size,
+        ])
+    }
+
     pub fn render(&mut self, args: &RenderArgs) {
         use graphics::*;
 
         self.fps.add_time(args.ext_dt);
+        self.poll_oscillators();
 
-        let msg = format!("fps: {0:.2}", self.fps.avg());
+        let msg = self.hud_message();
         self.calculate(args);
+        let selection = self.selection_rect();
+        let minimap = if self.show_minimap {
+            Some(self.minimap())
+        } else {
+            None
+        };
+        let line_start = self.line_start.map(|(row, col)| {
+            [
+                self.offset_x + col as f64 * self.cell_size,
+                self.offset_y + row as f64 * self.cell_size,
+                self.cell_size,
+                self.cell_size,
+            ]
+        });
+        let grid_lines = if self.show_grid {
+            self.grid_lines()
+        } else {
+            Vec::new()
+        };
+        let textured = self.color_mode == ColorMode::Solid && self.cell_shape == CellShape::Square;
+        let cells: Vec<(u32, u32, Color)> = if textured {
+            self.refresh_board();
+            Vec::new()
+        } else {
+            // the texture goes stale while it isn't being drawn
+            self.board = None;
+            self.universe
+                .live_cells()
+                .map(|(x, y)| (x, y, self.fill_color(y, x)))
+                .collect()
+        };
+        let fading = self.fading_cells();
+        let oscillators = self.oscillator_cells();
+        let trail = if self.show_trail {
+            self.trail_cells()
+        } else {
+            Vec::new()
+        };
+        let trail_color = life::color::lerp(self.bg_color, self.fg_color, Self::TRAIL_TINT);
+        let ghosts = if self.show_ghosts && self.universe.boundary() == Boundary::Toroidal {
+            self.ghost_cells()
+        } else {
+            Vec::new()
+        };
+        let board = if textured { self.board.as_ref() } else { None };
+        let board_rect = [
+            self.offset_x,
+            self.offset_y,
+            self.universe.width() as f64 * self.cell_size,
+            self.universe.height() as f64 * self.cell_size,
+        ];
         let square = self.square;
         let glyph_cache = &mut self.glyph_cache;
         let offset_x = self.offset_x;
         let offset_y = self.offset_y;
-        let live_cells = &self.universe.live_cells;
         let cell_size = self.cell_size;
+        let paused = self.paused;
+        let (bg_color, grid_color) = (self.bg_color, self.grid_color);
+        // one framebuffer pixel wide whatever the display
+        let grid_radius = 0.5 / self.pixel_ratio;
+        let cell_shape = self.cell_shape;
+        let fg_color = self.fg_color;
+        let show_help = self.show_help;
+        let view_size = self.view_size;
 
         self.gl.draw(args.viewport(), |c, gl| {
             // Clear the screen.
-            clear(Self::BG_COLOR, gl);
+            clear(bg_color, gl);
 
             // Draw the live cells
-            for (x, y) in live_cells.iter() {
-                let dx = *x as f64 * cell_size;
-                let dy = *y as f64 * cell_size;
+            if let Some(board) = board {
+                Image::new()
+                    .rect(board_rect)
+                    .draw(&board.texture, &DrawState::default(), c.transform, gl);
+            }
+            for (x, y) in trail {
+                let transform = c
+                    .transform
+                    .trans(offset_x + x as f64 * cell_size, offset_y + y as f64 * cell_size);
+                rectangle(trail_color, square, transform, gl);
+            }
+            for (x, y, fill) in fading.into_iter().chain(cells).chain(oscillators) {
+                let dx = x as f64 * cell_size;
+                let dy = y as f64 * cell_size;
                 let transform = c.transform.trans(offset_x, offset_y).trans(dx, dy);
 
-                rectangle(Self::FG_COLOR, square, transform, gl);
+                match cell_shape {
+                    CellShape::Square => rectangle(fill, square, transform, gl),
+                    CellShape::Circle => ellipse(fill, square, transform, gl),
+                }
+            }
+
+            for (x, y, fill) in ghosts {
+                let transform = c
+                    .transform
+                    .trans(offset_x + x * cell_size, offset_y + y * cell_size);
+                match cell_shape {
+                    CellShape::Square => rectangle(fill, square, transform, gl),
+                    CellShape::Circle => ellipse(fill, square, transform, gl),
+                }
+            }
+
+            for grid_line in grid_lines {
+                line(grid_color, grid_radius, grid_line, c.transform, gl);
+            }
+
+            if let Some(rect) = selection {
+                Rectangle::new(Self::SELECTION_FILL)
+                    .border(rectangle::Border {
+                        color: Self::SELECTION_COLOR,
+                        radius: grid_radius * 2.0,
+                    })
+                    .draw(rect, &DrawState::default(), c.transform, gl);
+            }
+            if let Some(rect) = line_start {
+                Rectangle::new_border(Self::SELECTION_COLOR, grid_radius * 2.0).draw(
+                    rect,
+                    &DrawState::default(),
+                    c.transform,
+                    gl,
+                );
             }
 
             // Draw the fps calculation
@@ -89,41 +1095,507 @@ impl ViewOfLife<'_> {
                     gl,
                 )
                 .unwrap();
+
+            // after the rest of the HUD, however long that turned out
+            let msg_width = glyph_cache.width(16, &msg).unwrap_or(0.0);
+            if paused {
+                text::Text::new_color([0.8, 0.0, 0.0, 1.0], 16)
+                    .draw(
+                        "PAUSED",
+                        glyph_cache,
+                        &DrawState::default(),
+                        c.transform.trans(10.0 + msg_width + 20.0, 15.0),
+                        gl,
+                    )
+                    .unwrap();
+            }
+
+            if let Some((outline, samples, viewport)) = minimap {
+                Rectangle::new(bg_color)
+                    .border(rectangle::Border {
+                        color: grid_color,
+                        radius: grid_radius * 2.0,
+                    })
+                    .draw(outline, &DrawState::default(), c.transform, gl);
+                for sample in samples {
+                    rectangle(fg_color, sample, c.transform, gl);
+                }
+                Rectangle::new_border(Self::SELECTION_COLOR, grid_radius * 2.0).draw(
+                    viewport,
+                    &DrawState::default(),
+                    c.transform,
+                    gl,
+                );
+            }
+
+            // over everything else, below the HUD line
+            if show_help {
+                let top = Self::TOP_MARGIN;
+                let height = HELP.len() as f64 * Self::HELP_LINE_HEIGHT + 20.0;
+                rectangle(
+                    Self::HELP_BACKGROUND,
+                    [0.0, top, view_size[0], height],
+                    c.transform,
+                    gl,
+                );
+                let text = text::Text::new_color(Self::HELP_COLOR, Self::HELP_FONT_SIZE);
+                for (i, (keys, action)) in HELP.iter().enumerate() {
+                    let baseline = top + 10.0 + (i + 1) as f64 * Self::HELP_LINE_HEIGHT - 6.0;
+                    for &(x, line) in &[(20.0, keys), (Self::HELP_ACTION_X, action)] {
+                        text.draw(
+                            line,
+                            glyph_cache,
+                            &DrawState::default(),
+                            c.transform.trans(x, baseline),
+                            gl,
+                        )
+                        .unwrap();
+                    }
+                }
+            }
         });
     }
 
-    pub fn update(&mut self, _args: &UpdateArgs) {
-        self.universe.update();
+    // Never run more than this many steps in a single update, so a slow
+    // frame doesn't snowball into ever more catching up.
+    const MAX_STEPS_PER_UPDATE: u32 = 16;
+    const DEFAULT_UPDATES_PER_SECOND: f64 = 30.0;
+    const DEFAULT_MAX_FPS: u64 = piston::event_loop::DEFAULT_MAX_FPS;
+    // the event loop needs some limit; a frame a nanosecond is none at all
+    const UNCAPPED_FPS: u64 = 1_000_000_000;
+    const MIN_UPDATES_PER_SECOND: f64 = 1.0;
+    const MAX_UPDATES_PER_SECOND: f64 = 240.0;
+
+    pub fn set_max_fps(&mut self, max_fps: u64) {
+        self.max_fps = max_fps.max(1);
     }
 
-    pub fn new(opengl: OpenGL, width: u32, height: u32) -> ViewOfLife<'static> {
-        let texture_settings = TextureSettings::new();
-        let glyph_cache = GlyphCache::new(
-            "/System/Library/Fonts/Supplemental/Futura.ttc",
-            (),
-            texture_settings,
-        )
-        .unwrap();
+    // what the event loop's max_fps should be set to
+    pub fn frame_cap(&self) -> u64 {
+        if self.uncapped {
+            Self::UNCAPPED_FPS
+        } else {
+            self.max_fps
+        }
+    }
+
+    pub fn set_updates_per_second(&mut self, updates_per_second: f64) {
+        self.updates_per_second = updates_per_second.clamp(
+            Self::MIN_UPDATES_PER_SECOND,
+            Self::MAX_UPDATES_PER_SECOND,
+        );
+        self.update_accumulator = 0.0;
+    }
+
+    pub fn press(&mut self, button: &Button) {
+        match button {
+            Button::Keyboard(Key::Space) => {
+                self.paused = !self.paused;
+                if !self.paused {
+                    // frame times from before the pause say nothing about now
+                    self.fps.reset();
+                }
+            }
+            // the arrow keys pan, so `.` stands in for Right
+            Button::Keyboard(Key::N) | Button::Keyboard(Key::Period) if self.paused => {
+                self.step_once = true
+            }
+            Button::Keyboard(Key::Left) => self.pan_by(Self::PAN_STEP, 0.0),
+            Button::Keyboard(Key::Right) => self.pan_by(-Self::PAN_STEP, 0.0),
+            Button::Keyboard(Key::Up) => self.pan_by(0.0, Self::PAN_STEP),
+            Button::Keyboard(Key::Down) => self.pan_by(0.0, -Self::PAN_STEP),
+            Button::Mouse(MouseButton::Middle) => self.panning = true,
+            Button::Keyboard(Key::G) => self.show_grid = !self.show_grid,
+            Button::Keyboard(Key::H) => self.show_help = !self.show_help,
+            Button::Keyboard(Key::W) => self.show_ghosts = !self.show_ghosts,
+            Button::Keyboard(Key::M) => self.show_minimap = !self.show_minimap,
+            Button::Keyboard(Key::P) => self.toggle_oscillators(),
+            Button::Keyboard(Key::T) if self.shift_held => self.clear_trail(),
+            Button::Keyboard(Key::T) => self.toggle_trail(),
+            Button::Keyboard(Key::U) => {
+                self.uncapped = !self.uncapped;
+                // the old rate says nothing about the new one
+                self.fps.reset();
+            }
+            Button::Keyboard(Key::B) => self.cycle_brush_size(),
+            Button::Keyboard(Key::L) => {
+                self.line_tool = !self.line_tool;
+                self.line_start = None;
+            }
+            Button::Keyboard(Key::LShift) | Button::Keyboard(Key::RShift) => {
+                self.shift_held = true
+            }
+            Button::Keyboard(Key::LCtrl)
+            | Button::Keyboard(Key::RCtrl)
+            | Button::Keyboard(Key::LGui)
+            | Button::Keyboard(Key::RGui) => self.ctrl_held = true,
+            Button::Keyboard(Key::C) if self.ctrl_held => self.copy_selection(),
+            Button::Keyboard(Key::V) if self.ctrl_held => self.paste(),
+            Button::Keyboard(Key::Z) if self.ctrl_held => {
+                self.full_repaint |= self.undo.undo(&mut self.universe)
+            }
+            Button::Keyboard(Key::Z) => self.zoom_to_fit(),
+            Button::Keyboard(Key::C) => self.set_color_mode(match self.color_mode {
+                ColorMode::Solid => ColorMode::Age,
+                ColorMode::Age => ColorMode::Solid,
+            }),
+            Button::Keyboard(Key::D1) => self.hud.toggle(HudFlags::FPS),
+            Button::Keyboard(Key::D2) => self.hud.toggle(HudFlags::UPS),
+            Button::Keyboard(Key::D3) => self.hud.toggle(HudFlags::GENERATION),
+            Button::Keyboard(Key::D4) => self.hud.toggle(HudFlags::POPULATION),
+            Button::Keyboard(Key::D5) => self.hud.toggle(HudFlags::CURSOR),
+            Button::Keyboard(Key::Backspace) => {
+                self.universe.reset();
+                self.undo.clear();
+                self.full_repaint = true;
+            }
+            Button::Keyboard(Key::Return) => {
+                let seed = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+                self.randomize(seed);
+            }
+            Button::Keyboard(Key::S) => self.screenshot(),
+            Button::Keyboard(Key::R) => self.toggle_recording(),
+            Button::Keyboard(Key::O) => self.set_cell_shape(match self.cell_shape {
+                CellShape::Square => CellShape::Circle,
+                CellShape::Circle => CellShape::Square,
+            }),
+            Button::Keyboard(Key::Equals) => {
+                self.set_updates_per_second(self.updates_per_second * 2.0)
+            }
+            Button::Keyboard(Key::Minus) => {
+                self.set_updates_per_second(self.updates_per_second / 2.0)
+            }
+            Button::Mouse(MouseButton::Left) if self.shift_held => {
+                if let Some((row, col)) = self.cell_at(self.cursor) {
+                    self.selection = Some((row, col, row, col));
+                    self.selecting = true;
+                }
+            }
+            Button::Mouse(MouseButton::Left) if self.line_tool => {
+                if let Some((row, col)) = self.cell_at(self.cursor) {
+                    match self.line_start.take() {
+                        Some((start_row, start_col)) => {
+                            self.undo.begin();
+                            for (row, col) in line_cells(start_row, start_col, row, col) {
+                                self.undo.record(&self.universe, row, col);
+                            }
+                            self.undo.end();
+                            self.universe.draw_line(start_row, start_col, row, col, Cell::Alive);
+                            self.full_repaint = true;
+                        }
+                        None => self.line_start = Some((row, col)),
+                    }
+                }
+            }
+            Button::Mouse(MouseButton::Left) => {
+                self.selection = None;
+                if let Some((row, col)) = self.cell_at(self.cursor) {
+                    // the brush takes on the opposite of the cell clicked
+                    let state = match self.universe.get_cell(row, col) {
+                        Cell::Alive => Cell::Dead,
+                        _ => Cell::Alive,
+                    };
+                    self.undo.begin();
+                    self.paint(row, col, state);
+                    self.drawing = Some(state);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn release(&mut self, button: &Button) {
+        match button {
+            Button::Mouse(MouseButton::Left) => {
+                self.drawing = None;
+                self.selecting = false;
+                self.undo.end();
+            }
+            Button::Mouse(MouseButton::Middle) => self.panning = false,
+            Button::Keyboard(Key::LShift) | Button::Keyboard(Key::RShift) => {
+                self.shift_held = false
+            }
+            Button::Keyboard(Key::LCtrl)
+            | Button::Keyboard(Key::RCtrl)
+            | Button::Keyboard(Key::LGui)
+            | Button::Keyboard(Key::RGui) => self.ctrl_held = false,
+            _ => {}
+        }
+    }
+
+    pub fn mouse_scroll(&mut self, scroll: [f64; 2]) {
+        self.zoom_by(1.1f64.powf(scroll[1]));
+    }
+
+    pub fn mouse_move(&mut self, position: [f64; 2]) {
+        if self.panning {
+            self.pan_by(position[0] - self.cursor[0], position[1] - self.cursor[1]);
+        }
+        if let Some(state) = self.drawing {
+            self.paint_line(self.cursor, position, state);
+        }
+        if self.selecting {
+            if let (Some((r0, c0, _, _)), Some((row, col))) =
+                (self.selection, self.cell_at(position))
+            {
+                self.selection = Some((r0, c0, row, col));
+            }
+        }
+        self.cursor = position;
+    }
+
+    pub fn update(&mut self, args: &UpdateArgs) {
+        if self.paused {
+            if self.step_once {
+                self.step_once = false;
+                self.step();
+            }
+            return;
+        }
+        let step = 1.0 / self.updates_per_second;
+        self.update_accumulator += args.dt;
+        let mut steps = 0;
+        while self.update_accumulator >= step {
+            self.update_accumulator -= step;
+            steps += 1;
+            if steps > Self::MAX_STEPS_PER_UPDATE {
+                self.update_accumulator = 0.0;
+                break;
+            }
+            self.step();
+        }
+    }
+
+    // the HUD font from `font` if it loads, otherwise the bundled one
+    fn load_font(font: Option<&Path>) -> GlyphCache<'static> {
+        if let Some(path) = font {
+            match GlyphCache::new(path, (), TextureSettings::new()) {
+                Ok(glyph_cache) => return glyph_cache,
+                Err(err) => eprintln!(
+                    "couldn't load font {}: {}; using the bundled font",
+                    path.display(),
+                    err
+                ),
+            }
+        }
+        GlyphCache::from_bytes(BUNDLED_FONT, (), TextureSettings::new())
+            .expect("the bundled font is a valid TrueType font")
+    }
+
+    pub fn new(
+        opengl: OpenGL,
+        universe: Universe,
+        font: Option<&Path>,
+    ) -> ViewOfLife<'static> {
+        let glyph_cache = Self::load_font(font);
 
         ViewOfLife {
             gl: GlGraphics::new(opengl),
             glyph_cache,
             fps: TimingBuffer::new(100),
+            hud: HudFlags::ALL,
             cell_size: 10.0,
             square: graphics::rectangle::square(0.0, 0.0, 10.0),
             offset_x: 0.0,
             offset_y: 0.0,
-            universe: Universe::new(width, height),
+            view_size: [0.0, 0.0],
+            pixel_ratio: 1.0,
+            zoom: 1.0,
+            pan: [0.0, 0.0],
+            panning: false,
+            show_grid: false,
+            bg_color: [1.0, 1.0, 1.0, 1.0],
+            fg_color: [0.0, 0.0, 0.0, 1.0],
+            grid_color: [0.75, 0.75, 0.75, 1.0],
+            color_mode: ColorMode::Solid,
+            young_color: Self::DEFAULT_YOUNG_COLOR,
+            old_color: Self::DEFAULT_OLD_COLOR,
+            max_age: Self::DEFAULT_MAX_AGE,
+            cell_shape: CellShape::Square,
+            board: None,
+            full_repaint: true,
+            last_seed: None,
+            recorder: None,
+            recording_scale: Self::DEFAULT_RECORDING_SCALE,
+            recording_delay: Self::DEFAULT_RECORDING_DELAY,
+            universe,
+            updates_per_second: Self::DEFAULT_UPDATES_PER_SECOND,
+            update_accumulator: 0.0,
+            paused: false,
+            step_once: false,
+            cursor: [0.0, 0.0],
+            drawing: None,
+            brush_size: 1,
+            line_tool: false,
+            line_start: None,
+            shift_held: false,
+            ctrl_held: false,
+            selection: None,
+            selecting: false,
+            clipboard: None,
+            undo: UndoStack::new(Self::UNDO_DEPTH),
+            show_help: false,
+            show_ghosts: false,
+            max_fps: Self::DEFAULT_MAX_FPS,
+            uncapped: false,
+            fade_frames: 0,
+            fading: HashMap::new(),
+            show_trail: false,
+            show_minimap: false,
+            show_oscillators: false,
+            oscillators: None,
+            scanning: None,
+            visited: Vec::new(),
         }
     }
 }
 
+// framebuffer pixels per window coordinate; 1.0 for a window or framebuffer
+// with no size, say while it's minimized
+fn device_pixel_ratio(window_size: [f64; 2], draw_size: [u32; 2]) -> f64 {
+    if window_size[0] > 0.0 && draw_size[0] > 0 {
+        draw_size[0] as f64 / window_size[0]
+    } else {
+        1.0
+    }
+}
+
+// The board re-fits itself on the next render, since `calculate` works from
+// whatever size the window turns out to be.
+fn toggle_fullscreen(window: &Window) {
+    let window = window.ctx.window();
+    if window.fullscreen().is_some() {
+        window.set_fullscreen(None);
+    } else {
+        window.set_fullscreen(Some(Fullscreen::Borderless(window.current_monitor())));
+    }
+}
+
+// reads a pattern file, or stdin for "-", in whichever format its extension
+// says, or failing that whichever it looks like
+fn load_pattern(path: &Path, width: u32, height: u32) -> Result<Universe, Box<dyn Error>> {
+    let text = if path == Path::new("-") {
+        let mut text = String::new();
+        std::io::stdin().read_to_string(&mut text)?;
+        text
+    } else {
+        std::fs::read_to_string(path)?
+    };
+    let format = Format::from_path(path).unwrap_or_else(|| Format::sniff(&text));
+    Ok(Universe::from_pattern(&text, format, width, height)?)
+}
+
+// Fills in whatever the command line left unset from `--config`, or from
+// life.toml when there is one.
+#[cfg(feature = "config")]
+fn load_config(options: &mut cli::Options) -> Result<(), Box<dyn Error>> {
+    let path = match &options.config {
+        Some(path) => path.clone(),
+        None if Path::new(config::DEFAULT_PATH).exists() => config::DEFAULT_PATH.into(),
+        None => return Ok(()),
+    };
+    let config = config::Config::load(&path)
+        .map_err(|err| format!("couldn't load {}: {}", path.display(), err))?;
+    config.apply(options)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "config"))]
+fn load_config(options: &mut cli::Options) -> Result<(), Box<dyn Error>> {
+    match &options.config {
+        Some(_) => Err("--config needs life built with the config feature".into()),
+        None => Ok(()),
+    }
+}
+
 fn main() {
+    let mut options = match cli::Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(2);
+        }
+    };
+    if options.help {
+        println!("{}", cli::USAGE);
+        return;
+    }
+    if let Err(err) = load_config(&mut options) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+
+    if let Some(soups) = options.soup_search {
+        let (size, seed) = (options.soup_size, options.seed.unwrap_or(0));
+        let rule = options.rule.unwrap_or_default();
+        headless::soup_search(soups, size, options.steps, seed, rule);
+        return;
+    }
+
+    let (width, height) = options.board_size();
+    let mut universe = match (&options.image, &options.pattern) {
+        (Some(path), _) => match Universe::from_image(path, options.threshold) {
+            Ok(universe) => universe,
+            Err(err) => {
+                eprintln!("couldn't load {}: {}", path.display(), err);
+                std::process::exit(1);
+            }
+        },
+        (None, Some(path)) => match load_pattern(path, width, height) {
+            Ok(universe) => universe,
+            Err(err) => {
+                eprintln!("couldn't load {}: {}", path.display(), err);
+                std::process::exit(1);
+            }
+        },
+        (None, None) => match options.seed {
+            Some(seed) => Universe::new_random(width, height, ViewOfLife::RANDOM_DENSITY, seed),
+            None => Universe::new(width, height),
+        },
+    };
+    if let Some(rule) = options.rule {
+        universe.set_rule(rule);
+    }
+    if let Some(boundary) = options.boundary {
+        universe.set_boundary(boundary);
+    }
+    if let Some(noise) = options.noise {
+        universe.set_noise(noise, options.seed.unwrap_or(0));
+    }
+
+    if options.bench {
+        headless::bench(universe, options.steps, options.engine);
+        return;
+    }
+
+    if options.headless {
+        if let Err(err) = headless::run(universe, &options) {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let updates_per_second = options.ups.unwrap_or(ViewOfLife::DEFAULT_UPDATES_PER_SECOND);
+    if options.renderer == cli::Renderer::Ascii {
+        if let Err(err) = terminal::run(universe, updates_per_second) {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     // Change this to OpenGL::V2_1 if not working.
     let opengl = OpenGL::V3_2;
 
     // Create an Glutin window.
-    let mut window: Window = WindowSettings::new("game-of-life", WINDOW_SIZE)
+    let window_size = Size {
+        width: options.width,
+        height: options.height,
+    };
+    let mut window: Window = WindowSettings::new("game-of-life", window_size)
         .graphics_api(opengl)
         .exit_on_esc(true)
         // .fullscreen(true)
@@ -131,16 +1603,92 @@ fn main() {
         .unwrap();
 
     // Create a new game and run it.
-    let mut app = ViewOfLife::new(opengl, WINDOW_SIZE.width as u32 / 2, WINDOW_SIZE.height as u32 / 2);
+    let mut app = ViewOfLife::new(opengl, universe, options.font.as_deref());
+    app.set_updates_per_second(updates_per_second);
+    if let Some(color) = options.fg_color {
+        app.set_fg_color(color);
+    }
+    if let Some(color) = options.bg_color {
+        app.set_bg_color(color);
+    }
+    if let Some(color) = options.grid_color {
+        app.set_grid_color(color);
+    }
+    if options.young_color.is_some() || options.old_color.is_some() || options.max_age.is_some() {
+        app.set_age_gradient(
+            options.young_color.unwrap_or(ViewOfLife::DEFAULT_YOUNG_COLOR),
+            options.old_color.unwrap_or(ViewOfLife::DEFAULT_OLD_COLOR),
+            options.max_age.unwrap_or(ViewOfLife::DEFAULT_MAX_AGE),
+        );
+    }
+
+    if let Some(max_fps) = options.max_fps {
+        app.set_max_fps(max_fps);
+    }
+    if let Some(fade_frames) = options.fade_frames {
+        app.set_fade_frames(fade_frames);
+    }
+    if options.record_scale.is_some() || options.record_delay.is_some() {
+        app.set_recording_options(
+            options.record_scale.unwrap_or(ViewOfLife::DEFAULT_RECORDING_SCALE),
+            options.record_delay.unwrap_or(ViewOfLife::DEFAULT_RECORDING_DELAY),
+        );
+    }
 
-    let mut events = Events::new(EventSettings::new());
+    let mut events = Events::new(EventSettings::new().max_fps(app.frame_cap()));
     while let Some(e) = events.next(&mut window) {
         if let Some(args) = e.render_args() {
             app.render(&args);
         }
 
+        if let Some(position) = e.mouse_cursor_args() {
+            app.mouse_move(position);
+        }
+
+        if let Some(scroll) = e.mouse_scroll_args() {
+            app.mouse_scroll(scroll);
+        }
+
+        if let Some(button) = e.press_args() {
+            if button == Button::Keyboard(Key::F) {
+                toggle_fullscreen(&window);
+            }
+            app.press(&button);
+            if button == Button::Keyboard(Key::U) {
+                events.set_max_fps(app.frame_cap());
+            }
+        }
+
+        if let Some(button) = e.release_args() {
+            app.release(&button);
+        }
+
         if let Some(args) = e.update_args() {
             app.update(&args);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pixel_ratio_of_standard_and_retina_displays() {
+        assert_eq!(device_pixel_ratio([1440.0, 900.0], [1440, 900]), 1.0);
+        assert_eq!(device_pixel_ratio([1440.0, 900.0], [2880, 1800]), 2.0);
+    }
+
+    #[test]
+    fn pixel_ratio_can_be_fractional() {
+        assert_eq!(device_pixel_ratio([1280.0, 720.0], [1600, 900]), 1.25);
+        assert_eq!(device_pixel_ratio([1000.0, 500.0], [1500, 750]), 1.5);
+    }
+
+    #[test]
+    fn pixel_ratio_of_an_empty_window_is_one() {
+        assert_eq!(device_pixel_ratio([0.0, 0.0], [0, 0]), 1.0);
+        assert_eq!(device_pixel_ratio([0.0, 0.0], [2880, 1800]), 1.0);
+        assert_eq!(device_pixel_ratio([1440.0, 900.0], [0, 0]), 1.0);
+    }
+}
@@ -0,0 +1,292 @@
+use crate::game_of_life::{Boundary, Cell, Neighborhood, Universe};
+use crate::rule::Rule;
+
+// A universe that stores one bit per cell instead of one byte, for boards
+// too big to hold as `Vec<Cell>`. Cells are addressed the same way as in
+// `Universe` and it follows the same rule, boundary and neighborhood, so the
+// two can be swapped for plain two-state rules; dying cells of Generations
+// rules can't be stored and are treated as dead.
+pub struct PackedUniverse {
+    width: u32,
+    height: u32,
+    words: Vec<u64>,
+    next_words: Vec<u64>,
+    generation: u64,
+    rule: Rule,
+    boundary: Boundary,
+    neighborhood: Neighborhood,
+}
+
+impl PackedUniverse {
+    pub fn new(width: u32, height: u32) -> PackedUniverse {
+        let len = PackedUniverse::word_count(width, height);
+        PackedUniverse {
+            width,
+            height,
+            words: vec![0; len],
+            next_words: vec![0; len],
+            generation: 0,
+            rule: Rule::default(),
+            boundary: Boundary::Toroidal,
+            neighborhood: Neighborhood::Moore,
+        }
+    }
+
+    // packs the live cells, rule, boundary and neighborhood of a
+    // byte-per-cell universe
+    pub fn from_universe(universe: &Universe) -> PackedUniverse {
+        let mut packed = PackedUniverse::new(universe.width(), universe.height());
+        packed.rule = universe.rule();
+        packed.boundary = universe.boundary();
+        packed.neighborhood = universe.neighborhood();
+        for (col, row) in universe.live_cells() {
+            packed.set_cell(row, col, Cell::Alive);
+        }
+        packed
+    }
+
+    fn word_count(width: u32, height: u32) -> usize {
+        (width as usize * height as usize).div_ceil(64)
+    }
+
+    fn get_index(&self, row: u32, column: u32) -> usize {
+        (row * self.width + column) as usize
+    }
+
+    fn bit(&self, idx: usize) -> bool {
+        self.words[idx / 64] & (1 << (idx % 64)) != 0
+    }
+
+    // bytes used by the cell storage, both generations
+    pub fn storage_bytes(&self) -> usize {
+        (self.words.len() + self.next_words.len()) * std::mem::size_of::<u64>()
+    }
+
+    pub fn get_cell(&self, row: u32, col: u32) -> Cell {
+        if row < self.height && col < self.width && self.bit(self.get_index(row, col)) {
+            Cell::Alive
+        } else {
+            Cell::Dead
+        }
+    }
+
+    pub fn set_cell(&mut self, row: u32, col: u32, state: Cell) {
+        if row >= self.height || col >= self.width {
+            return;
+        }
+        let idx = self.get_index(row, col);
+        match state {
+            Cell::Alive => self.words[idx / 64] |= 1 << (idx % 64),
+            _ => self.words[idx / 64] &= !(1 << (idx % 64)),
+        }
+    }
+
+    pub fn toggle_cell(&mut self, row: u32, col: u32) {
+        if row < self.height && col < self.width {
+            let idx = self.get_index(row, col);
+            self.words[idx / 64] ^= 1 << (idx % 64);
+        }
+    }
+
+    pub fn population(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    pub fn set_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+    }
+
+    pub fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    pub fn set_boundary(&mut self, boundary: Boundary) {
+        self.boundary = boundary;
+    }
+
+    pub fn neighborhood(&self) -> Neighborhood {
+        self.neighborhood
+    }
+
+    pub fn set_neighborhood(&mut self, neighborhood: Neighborhood) {
+        self.neighborhood = neighborhood;
+    }
+
+    // live cells as (col, row), walking only the set bits
+    pub fn live_cells(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        let width = self.width.max(1) as usize;
+        self.words
+            .iter()
+            .enumerate()
+            .flat_map(move |(i, &word)| {
+                let mut bits = word;
+                std::iter::from_fn(move || {
+                    if bits == 0 {
+                        return None;
+                    }
+                    let bit = bits.trailing_zeros() as usize;
+                    bits &= bits - 1;
+                    Some(i * 64 + bit)
+                })
+            })
+            .map(move |idx| ((idx % width) as u32, (idx / width) as u32))
+    }
+
+    fn live_neighbor_count(&self, row: u32, column: u32) -> u8 {
+        let height = self.height as i64;
+        let width = self.width as i64;
+        let mut count = 0;
+        for delta_row in [-1, 0, 1].iter().cloned() {
+            for delta_col in [-1, 0, 1].iter().cloned() {
+                let diagonal = delta_row != 0 && delta_col != 0;
+                if (delta_row == 0 && delta_col == 0)
+                    || (diagonal && self.neighborhood == Neighborhood::VonNeumann)
+                {
+                    continue;
+                }
+
+                let mut neighbor_row = row as i64 + delta_row;
+                let mut neighbor_col = column as i64 + delta_col;
+                match self.boundary {
+                    Boundary::Toroidal => {
+                        neighbor_row = neighbor_row.rem_euclid(height);
+                        neighbor_col = neighbor_col.rem_euclid(width);
+                    }
+                    Boundary::Dead => {
+                        if neighbor_row < 0
+                            || neighbor_row >= height
+                            || neighbor_col < 0
+                            || neighbor_col >= width
+                        {
+                            continue;
+                        }
+                    }
+                }
+                let idx = self.get_index(neighbor_row as u32, neighbor_col as u32);
+                count += self.bit(idx) as u8;
+            }
+        }
+        count
+    }
+
+    // steps one generation, returning whether any cell changed
+    pub fn update(&mut self) -> bool {
+        let mut next = std::mem::take(&mut self.next_words);
+        for word in next.iter_mut() {
+            *word = 0;
+        }
+
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let idx = self.get_index(row, col);
+                let live_neighbors = self.live_neighbor_count(row, col);
+                let alive = if self.bit(idx) {
+                    self.rule.survives(live_neighbors)
+                } else {
+                    self.rule.is_born(live_neighbors)
+                };
+                if alive {
+                    next[idx / 64] |= 1 << (idx % 64);
+                }
+            }
+        }
+
+        let changed = next != self.words;
+        self.next_words = std::mem::replace(&mut self.words, next);
+        self.generation += 1;
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells_of_universe(universe: &Universe) -> Vec<(u32, u32)> {
+        let mut cells: Vec<(u32, u32)> = universe.live_cells().collect();
+        cells.sort_unstable();
+        cells
+    }
+
+    fn cells_of_packed(packed: &PackedUniverse) -> Vec<(u32, u32)> {
+        let mut cells: Vec<(u32, u32)> = packed.live_cells().collect();
+        cells.sort_unstable();
+        cells
+    }
+
+    #[test]
+    fn matches_universe_on_random_boards() {
+        let settings = [
+            (Boundary::Toroidal, Neighborhood::Moore, Rule::conway()),
+            (Boundary::Dead, Neighborhood::Moore, Rule::highlife()),
+            (
+                Boundary::Toroidal,
+                Neighborhood::VonNeumann,
+                Rule::new(&[1], &[1, 2]),
+            ),
+            (
+                Boundary::Dead,
+                Neighborhood::VonNeumann,
+                Rule::new(&[1, 3], &[0, 2]),
+            ),
+        ];
+        for (seed, &(boundary, neighborhood, rule)) in settings.iter().enumerate() {
+            // a width that isn't a multiple of 64, so rows straddle words
+            let mut universe = Universe::new_random(77, 45, 0.4, seed as u64);
+            universe.set_rule(rule);
+            universe.set_boundary(boundary);
+            universe.set_neighborhood(neighborhood);
+            let mut packed = PackedUniverse::from_universe(&universe);
+            assert_eq!(packed.neighborhood(), neighborhood);
+            for generation in 0..40 {
+                assert_eq!(packed.update(), universe.update());
+                assert_eq!(
+                    cells_of_packed(&packed),
+                    cells_of_universe(&universe),
+                    "{:?} {:?} gen {}",
+                    boundary,
+                    neighborhood,
+                    generation
+                );
+                assert_eq!(packed.population(), universe.population());
+            }
+        }
+    }
+
+    #[test]
+    fn cell_access_matches_universe() {
+        let mut packed = PackedUniverse::new(70, 3);
+        packed.set_cell(1, 65, Cell::Alive);
+        packed.toggle_cell(2, 0);
+        packed.toggle_cell(2, 0);
+        packed.toggle_cell(0, 63);
+        packed.set_cell(3, 0, Cell::Alive);
+        packed.set_cell(0, 70, Cell::Alive);
+        assert_eq!(packed.get_cell(1, 65), Cell::Alive);
+        assert_eq!(packed.get_cell(2, 0), Cell::Dead);
+        assert_eq!(cells_of_packed(&packed), vec![(63, 0), (65, 1)]);
+        assert_eq!(packed.population(), 2);
+    }
+
+    #[test]
+    fn uses_a_bit_per_cell() {
+        let universe = Universe::new_random(1024, 1024, 0.5, 1);
+        let packed = PackedUniverse::from_universe(&universe);
+        // two generations of 1024 * 1024 bits
+        assert_eq!(packed.storage_bytes(), 2 * 1024 * 1024 / 8);
+        // at least 8x less than the two byte-per-cell grids of a universe
+        let cells = universe.width() as usize * universe.height() as usize;
+        assert!(packed.storage_bytes() * 8 <= 2 * cells * std::mem::size_of::<Cell>());
+    }
+}
@@ -0,0 +1,148 @@
+// Well known patterns as live (row, col) cells with the top-left of their
+// bounding box at (0, 0), ready for `Universe::insert_pattern`.
+
+pub fn glider() -> Vec<(u32, u32)> {
+    cells(&[
+        ".O.", //
+        "..O", //
+        "OOO", //
+    ])
+}
+
+pub fn blinker() -> Vec<(u32, u32)> {
+    cells(&["OOO"])
+}
+
+pub fn toad() -> Vec<(u32, u32)> {
+    cells(&[
+        ".OOO", //
+        "OOO.", //
+    ])
+}
+
+pub fn beacon() -> Vec<(u32, u32)> {
+    cells(&[
+        "OO..", //
+        "OO..", //
+        "..OO", //
+        "..OO", //
+    ])
+}
+
+pub fn pulsar() -> Vec<(u32, u32)> {
+    cells(&[
+        "..OOO...OOO..",
+        ".............",
+        "O....O.O....O",
+        "O....O.O....O",
+        "O....O.O....O",
+        "..OOO...OOO..",
+        ".............",
+        "..OOO...OOO..",
+        "O....O.O....O",
+        "O....O.O....O",
+        "O....O.O....O",
+        ".............",
+        "..OOO...OOO..",
+    ])
+}
+
+pub fn gosper_glider_gun() -> Vec<(u32, u32)> {
+    cells(&[
+        "........................O...........",
+        "......................O.O...........",
+        "............OO......OO............OO",
+        "...........O...O....OO............OO",
+        "OO........O.....O...OO..............",
+        "OO........O...O.OO....O.O...........",
+        "..........O.....O.......O...........",
+        "...........O...O....................",
+        "............OO......................",
+    ])
+}
+
+// lightweight spaceship, travelling right
+pub fn lwss() -> Vec<(u32, u32)> {
+    cells(&[
+        "O..O.", //
+        "....O", //
+        "O...O", //
+        ".OOOO", //
+    ])
+}
+
+fn cells(rows: &[&str]) -> Vec<(u32, u32)> {
+    let mut cells = Vec::new();
+    for (row, line) in rows.iter().enumerate() {
+        for (col, c) in line.chars().enumerate() {
+            if c == 'O' {
+                cells.push((row as u32, col as u32));
+            }
+        }
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_of_life::{Cell, Universe};
+
+    // (cells, height, width) of a pattern's bounding box
+    fn extent(cells: &[(u32, u32)]) -> (usize, u32, u32) {
+        let min_row = cells.iter().map(|&(row, _)| row).min().unwrap();
+        let min_col = cells.iter().map(|&(_, col)| col).min().unwrap();
+        let max_row = cells.iter().map(|&(row, _)| row).max().unwrap();
+        let max_col = cells.iter().map(|&(_, col)| col).max().unwrap();
+        assert_eq!((min_row, min_col), (0, 0));
+        (cells.len(), max_row + 1, max_col + 1)
+    }
+
+    fn board(cells: &[(u32, u32)], width: u32, height: u32, row: u32, col: u32) -> Universe {
+        let mut universe = Universe::new_random(width, height, 0.0, 0);
+        universe.insert_pattern(cells, row, col);
+        universe
+    }
+
+    #[test]
+    fn cell_counts_and_bounding_boxes() {
+        assert_eq!(extent(&glider()), (5, 3, 3));
+        assert_eq!(extent(&blinker()), (3, 1, 3));
+        assert_eq!(extent(&toad()), (6, 2, 4));
+        assert_eq!(extent(&beacon()), (8, 4, 4));
+        assert_eq!(extent(&pulsar()), (48, 13, 13));
+        assert_eq!(extent(&gosper_glider_gun()), (36, 9, 36));
+        assert_eq!(extent(&lwss()), (9, 4, 5));
+    }
+
+    #[test]
+    fn oscillators_have_their_periods() {
+        for (cells, period) in [(blinker(), 2), (toad(), 2), (beacon(), 2), (pulsar(), 3)] {
+            let mut universe = board(&cells, 20, 20, 3, 3);
+            assert_eq!(universe.detect_period(10), Some(period));
+        }
+    }
+
+    #[test]
+    fn lwss_travels_right() {
+        let mut universe = board(&lwss(), 20, 10, 3, 3);
+        for _ in 0..4 {
+            universe.update();
+        }
+        assert!(universe == board(&lwss(), 20, 10, 3, 5));
+    }
+
+    #[test]
+    fn gun_fires_a_glider_every_30_generations() {
+        let mut universe = board(&gosper_glider_gun(), 60, 40, 1, 1);
+        universe.set_boundary(crate::game_of_life::Boundary::Dead);
+        for _ in 0..30 {
+            universe.update();
+        }
+        // the gun is back where it started, plus one glider
+        assert_eq!(universe.population(), 36 + 5);
+        for (row, col) in gosper_glider_gun() {
+            assert_eq!(universe.get_cell(row + 1, col + 1), Cell::Alive);
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use crate::color::Color;
+use crate::game_of_life::{Cell, Universe};
+use image::{ImageResult, Rgba, RgbaImage};
+use std::path::Path;
+
+// Offscreen rendering straight from the cell data, for screenshots and
+// recordings, without going through OpenGL.
+
+pub fn to_rgba(color: Color) -> Rgba<u8> {
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    Rgba([
+        channel(color[0]),
+        channel(color[1]),
+        channel(color[2]),
+        channel(color[3]),
+    ])
+}
+
+/// Draws the board at `scale` pixels per cell, each live cell filled with
+/// `fill(row, col)`.
+///
+/// ```
+/// use life::game_of_life::Universe;
+/// use life::raster::render;
+///
+/// let universe = Universe::from_plaintext("O.\n.O").unwrap();
+/// let image = render(&universe, 3, [1.0, 1.0, 1.0, 1.0], |_, _| [0.0, 0.0, 0.0, 1.0]);
+/// assert_eq!(image.dimensions(), (6, 6));
+/// assert_eq!(image.get_pixel(1, 1).0, [0, 0, 0, 255]);
+/// assert_eq!(image.get_pixel(4, 1).0, [255, 255, 255, 255]);
+/// assert_eq!(image.get_pixel(5, 5).0, [0, 0, 0, 255]);
+/// ```
+pub fn render<F>(universe: &Universe, scale: u32, background: Color, fill: F) -> RgbaImage
+where
+    F: Fn(u32, u32) -> Color,
+{
+    let scale = scale.max(1);
+    let mut image = RgbaImage::from_pixel(
+        universe.width() * scale,
+        universe.height() * scale,
+        to_rgba(background),
+    );
+    for (col, row) in universe.live_cells() {
+        let color = to_rgba(fill(row, col));
+        for y in row * scale..(row + 1) * scale {
+            for x in col * scale..(col + 1) * scale {
+                image.put_pixel(x, y, color);
+            }
+        }
+    }
+    image
+}
+
+impl Universe {
+    /// Loads an image as a board of the same size, with every pixel darker
+    /// than `threshold` in grayscale alive.
+    ///
+    /// ```
+    /// use life::game_of_life::Universe;
+    ///
+    /// let path = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/fixtures/checkerboard.png");
+    /// let universe = Universe::from_image(path.as_ref(), 128).unwrap();
+    /// assert_eq!((universe.width(), universe.height()), (2, 2));
+    /// assert_eq!(universe.to_plaintext(), "O.\n.O\n");
+    /// assert_eq!(Universe::from_image(path.as_ref(), 0).unwrap().population(), 0);
+    /// ```
+    pub fn from_image(path: &Path, threshold: u8) -> ImageResult<Universe> {
+        let image = image::open(path)?.to_luma8();
+        let cells = image
+            .pixels()
+            .map(|pixel| {
+                if pixel.0[0] < threshold {
+                    Cell::Alive
+                } else {
+                    Cell::Dead
+                }
+            })
+            .collect();
+        Ok(Universe::with_cells(image.width(), image.height(), cells))
+    }
+}
@@ -0,0 +1,112 @@
+use gif::{Encoder, EncodingError, Frame, Repeat};
+use image::RgbaImage;
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::Path;
+
+// Appends frames to an animated GIF as they're rendered. The file is
+// finished off when the recorder is dropped.
+pub struct GifRecorder {
+    encoder: Encoder<BufWriter<File>>,
+    width: u16,
+    height: u16,
+    // hundredths of a second each frame is shown for
+    delay: u16,
+}
+
+impl GifRecorder {
+    pub fn create<P: AsRef<Path>>(
+        path: P,
+        width: u32,
+        height: u32,
+        delay: u16,
+    ) -> Result<GifRecorder, EncodingError> {
+        let too_big = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{}x{} is too big for a GIF", width, height),
+            )
+        };
+        let width = u16::try_from(width).map_err(|_| too_big())?;
+        let height = u16::try_from(height).map_err(|_| too_big())?;
+
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = Encoder::new(file, width, height, &[])?;
+        encoder.set_repeat(Repeat::Infinite)?;
+        Ok(GifRecorder {
+            encoder,
+            width,
+            height,
+            delay,
+        })
+    }
+
+    // frames have to be the size the recording was created with
+    pub fn add_frame(&mut self, image: &RgbaImage) -> Result<(), EncodingError> {
+        if image.dimensions() != (self.width as u32, self.height as u32) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "frame size doesn't match the recording",
+            )
+            .into());
+        }
+        let mut pixels = image.clone().into_raw();
+        let mut frame = Frame::from_rgba_speed(self.width, self.height, &mut pixels, 10);
+        frame.delay = self.delay;
+        self.encoder.write_frame(&frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("life-{}-{}.gif", std::process::id(), name))
+    }
+
+    #[test]
+    fn writes_every_frame() {
+        let path = temp_path("frames");
+        {
+            let mut recorder = GifRecorder::create(&path, 4, 3, 5).unwrap();
+            for shade in &[0, 128, 255] {
+                let image = RgbaImage::from_pixel(4, 3, Rgba([*shade, *shade, *shade, 255]));
+                recorder.add_frame(&image).unwrap();
+            }
+        }
+
+        let mut decoder = gif::DecodeOptions::new()
+            .read_info(File::open(&path).unwrap())
+            .unwrap();
+        assert_eq!((decoder.width(), decoder.height()), (4, 3));
+        let mut frames = 0;
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            assert_eq!(frame.delay, 5);
+            frames += 1;
+        }
+        assert_eq!(frames, 3);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_a_frame_of_the_wrong_size() {
+        let path = temp_path("wrong-size");
+        let mut recorder = GifRecorder::create(&path, 4, 3, 5).unwrap();
+        assert!(recorder.add_frame(&RgbaImage::new(3, 4)).is_err());
+        drop(recorder);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_sizes_a_gif_cant_hold() {
+        let path = temp_path("too-big");
+        assert!(GifRecorder::create(&path, 70_000, 10, 5).is_err());
+        assert!(GifRecorder::create(&path, 10, 70_000, 5).is_err());
+        // checked before anything is written
+        assert!(!path.exists());
+    }
+}
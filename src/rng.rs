@@ -0,0 +1,71 @@
+// Small xorshift64* generator so seeded soups don't need an external crate.
+// Not suitable for anything but reproducible boards.
+#[derive(Clone)]
+pub struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    pub fn new(seed: u64) -> XorShift {
+        // run the seed through splitmix64 so small or zero seeds still give
+        // a well mixed, non-zero starting state
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        XorShift {
+            state: if z == 0 { 0x2545_F491_4F6C_DD1D } else { z },
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.state = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    // uniform in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn take(rng: &mut XorShift, n: usize) -> Vec<u64> {
+        (0..n).map(|_| rng.next_u64()).collect()
+    }
+
+    #[test]
+    fn same_seed_same_sequence() {
+        assert_eq!(
+            take(&mut XorShift::new(42), 100),
+            take(&mut XorShift::new(42), 100)
+        );
+        assert_ne!(
+            take(&mut XorShift::new(42), 100),
+            take(&mut XorShift::new(43), 100)
+        );
+    }
+
+    #[test]
+    fn zero_seed_still_varies() {
+        let values = take(&mut XorShift::new(0), 100);
+        assert!(values.iter().all(|&value| value != 0));
+        assert!(values.windows(2).all(|pair| pair[0] != pair[1]));
+    }
+
+    #[test]
+    fn next_f64_is_in_the_unit_interval() {
+        let mut rng = XorShift::new(7);
+        let samples: Vec<f64> = (0..10_000).map(|_| rng.next_f64()).collect();
+        assert!(samples.iter().all(|&x| (0.0..1.0).contains(&x)));
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        assert!((mean - 0.5).abs() < 0.02, "mean {}", mean);
+    }
+}
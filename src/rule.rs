@@ -0,0 +1,328 @@
+use crate::error::ParseError;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+// A life-like rule in B/S notation. Bit n of `birth` is set when a dead cell
+// with n live neighbors is born, bit n of `survival` when a live cell with n
+// live neighbors survives.
+//
+// With more than two `states` it's a Generations rule: a live cell that
+// doesn't survive passes through `states - 2` dying states before it's dead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(into = "String", try_from = "String")
+)]
+pub struct Rule {
+    birth: u16,
+    survival: u16,
+    states: u8,
+}
+
+impl Rule {
+    // panics on a neighbor count above 8, like `generations`
+    pub fn new(birth: &[u8], survival: &[u8]) -> Rule {
+        Rule::generations(birth, survival, 2)
+    }
+
+    // panics on a neighbor count above 8; `try_generations` is for counts
+    // that come from outside
+    pub fn generations(birth: &[u8], survival: &[u8], states: u8) -> Rule {
+        Rule::try_generations(birth, survival, states).expect("neighbor counts go up to 8")
+    }
+
+    /// Like `generations`, but rejects neighbor counts no cell can have.
+    ///
+    /// ```
+    /// use life::rule::Rule;
+    /// assert_eq!(Rule::try_generations(&[2], &[], 3), Ok(Rule::brians_brain()));
+    /// assert!(Rule::try_generations(&[3], &[2, 9], 2).is_err());
+    /// ```
+    pub fn try_generations(birth: &[u8], survival: &[u8], states: u8) -> Result<Rule, ParseError> {
+        Ok(Rule {
+            birth: Rule::mask(birth)?,
+            survival: Rule::mask(survival)?,
+            states: states.max(2),
+        })
+    }
+
+    /// Conway's Game of Life.
+    ///
+    /// ```
+    /// use life::rule::Rule;
+    /// assert_eq!(Rule::conway(), "B3/S23".parse().unwrap());
+    /// ```
+    pub fn conway() -> Rule {
+        Rule::new(&[3], &[2, 3])
+    }
+
+    /// HighLife, Conway plus birth on 6; home of the replicator.
+    ///
+    /// ```
+    /// use life::rule::Rule;
+    /// assert_eq!(Rule::highlife(), "B36/S23".parse().unwrap());
+    /// ```
+    pub fn highlife() -> Rule {
+        Rule::new(&[3, 6], &[2, 3])
+    }
+
+    /// Day & Night, symmetric under swapping live and dead cells.
+    ///
+    /// ```
+    /// use life::rule::Rule;
+    /// assert_eq!(Rule::day_and_night(), "B3678/S34678".parse().unwrap());
+    /// ```
+    pub fn day_and_night() -> Rule {
+        Rule::new(&[3, 6, 7, 8], &[3, 4, 6, 7, 8])
+    }
+
+    /// Seeds, where every live cell dies each generation.
+    ///
+    /// ```
+    /// use life::rule::Rule;
+    /// assert_eq!(Rule::seeds(), "B2/S".parse().unwrap());
+    /// ```
+    pub fn seeds() -> Rule {
+        Rule::new(&[2], &[])
+    }
+
+    /// Replicator, in which every pattern eventually copies itself.
+    ///
+    /// ```
+    /// use life::rule::Rule;
+    /// assert_eq!(Rule::replicator(), "B1357/S1357".parse().unwrap());
+    /// ```
+    pub fn replicator() -> Rule {
+        Rule::new(&[1, 3, 5, 7], &[1, 3, 5, 7])
+    }
+
+    /// Brian's Brain, a Generations rule where every live cell dies after one
+    /// generation and then spends one more refractory.
+    ///
+    /// ```
+    /// use life::rule::Rule;
+    /// assert_eq!(Rule::brians_brain(), "B2/S/3".parse().unwrap());
+    /// ```
+    pub fn brians_brain() -> Rule {
+        Rule::generations(&[2], &[], 3)
+    }
+
+    /// Number of cell states, 2 for ordinary life-like rules.
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+
+    /// Neighbor counts on which a dead cell is born, in ascending order.
+    ///
+    /// ```
+    /// use life::rule::Rule;
+    /// assert_eq!(Rule::highlife().birth_counts(), vec![3, 6]);
+    /// ```
+    pub fn birth_counts(&self) -> Vec<u8> {
+        Rule::counts(self.birth)
+    }
+
+    /// Neighbor counts on which a live cell survives, in ascending order.
+    ///
+    /// ```
+    /// use life::rule::Rule;
+    /// assert_eq!(Rule::highlife().survival_counts(), vec![2, 3]);
+    /// ```
+    pub fn survival_counts(&self) -> Vec<u8> {
+        Rule::counts(self.survival)
+    }
+
+    pub fn is_born(&self, live_neighbors: u8) -> bool {
+        self.birth & (1 << live_neighbors) != 0
+    }
+
+    pub fn survives(&self, live_neighbors: u8) -> bool {
+        self.survival & (1 << live_neighbors) != 0
+    }
+
+    fn mask(counts: &[u8]) -> Result<u16, ParseError> {
+        let mut mask = 0;
+        for &n in counts {
+            if n > 8 {
+                return Err(ParseError::new(format!("no cell has {} neighbors", n)));
+            }
+            mask |= 1 << n;
+        }
+        Ok(mask)
+    }
+
+    fn counts(mask: u16) -> Vec<u8> {
+        (0..=8).filter(|n| mask & (1 << n) != 0).collect()
+    }
+
+    fn parse_counts(digits: &str, rule: &str) -> Result<u16, ParseError> {
+        let invalid = || ParseError::new(format!("invalid rule '{}'", rule));
+        let counts = digits
+            .chars()
+            .map(|c| c.to_digit(10).map(|n| n as u8).ok_or_else(invalid))
+            .collect::<Result<Vec<u8>, ParseError>>()?;
+        Rule::mask(&counts).map_err(|_| invalid())
+    }
+
+    fn write_counts(f: &mut fmt::Formatter, mask: u16) -> fmt::Result {
+        for n in Rule::counts(mask) {
+            write!(f, "{}", n)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Rule {
+        Rule::conway()
+    }
+}
+
+// Accepts "B3/S23" style (either order, any case) as well as the older
+// "23/3" survival/birth form found in many pattern files. Generations rules
+// add the number of states as a third part, as in "B2/S/3".
+impl FromStr for Rule {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Rule, ParseError> {
+        let rule = s.trim();
+        let mut parts: Vec<&str> = rule.split('/').collect();
+        let states = match parts.len() {
+            2 => 2,
+            3 => {
+                let states = parts.pop().unwrap();
+                let states = states.trim_start_matches(['C', 'c']);
+                match states.parse::<u8>() {
+                    Ok(states) if states >= 2 => states,
+                    _ => return Err(ParseError::new(format!("invalid rule '{}'", rule))),
+                }
+            }
+            _ => return Err(ParseError::new(format!("invalid rule '{}'", rule))),
+        };
+
+        let mut birth = None;
+        let mut survival = None;
+        for part in parts.iter() {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some('B') | Some('b') => birth = Some(Rule::parse_counts(chars.as_str(), rule)?),
+                Some('S') | Some('s') => survival = Some(Rule::parse_counts(chars.as_str(), rule)?),
+                _ => {}
+            }
+        }
+
+        match (birth, survival) {
+            (Some(birth), Some(survival)) => Ok(Rule {
+                birth,
+                survival,
+                states,
+            }),
+            (None, None) => Ok(Rule {
+                survival: Rule::parse_counts(parts[0], rule)?,
+                birth: Rule::parse_counts(parts[1], rule)?,
+                states,
+            }),
+            _ => Err(ParseError::new(format!("invalid rule '{}'", rule))),
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "B")?;
+        Rule::write_counts(f, self.birth)?;
+        write!(f, "/S")?;
+        Rule::write_counts(f, self.survival)?;
+        if self.states > 2 {
+            write!(f, "/{}", self.states)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<Rule> for String {
+    fn from(rule: Rule) -> String {
+        rule.to_string()
+    }
+}
+
+impl TryFrom<String> for Rule {
+    type Error = ParseError;
+
+    fn try_from(rule: String) -> Result<Rule, ParseError> {
+        rule.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_round_trips_through_parse() {
+        for &rule in &[
+            "B3/S23",
+            "B36/S23",
+            "B2/S",
+            "B/S012345678",
+            "B2/S/3",
+            "B35/S236/5",
+        ] {
+            let parsed: Rule = rule.parse().unwrap();
+            assert_eq!(parsed.to_string(), rule);
+            assert_eq!(parsed.to_string().parse::<Rule>().unwrap(), parsed);
+        }
+    }
+
+    #[test]
+    fn other_spellings_display_as_bs_notation() {
+        assert_eq!("23/3".parse::<Rule>().unwrap().to_string(), "B3/S23");
+        assert_eq!("s23/b36".parse::<Rule>().unwrap().to_string(), "B36/S23");
+        assert_eq!("B2/S/C3".parse::<Rule>().unwrap(), Rule::brians_brain());
+    }
+
+    #[test]
+    fn rejects_malformed_rules() {
+        for &rule in &[
+            "",
+            "B3",
+            "B9/S23",
+            "B3/S2x",
+            "B3/S23/1",
+            "B3/S23/4/5",
+            "B3/B23",
+        ] {
+            assert!(rule.parse::<Rule>().is_err(), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn counts_follow_the_notation() {
+        let rule: Rule = "B36/S23".parse().unwrap();
+        assert!(rule.is_born(3) && rule.is_born(6) && !rule.is_born(2));
+        assert!(rule.survives(2) && rule.survives(3) && !rule.survives(6));
+        assert_eq!(rule.states(), 2);
+    }
+
+    #[test]
+    fn rejects_neighbor_counts_above_eight() {
+        for &n in &[9, 15, 16, 255] {
+            assert!(Rule::try_generations(&[n], &[2, 3], 2).is_err(), "{}", n);
+            assert!(Rule::try_generations(&[3], &[n], 2).is_err(), "{}", n);
+        }
+        assert_eq!(
+            Rule::try_generations(&[3], &[0, 8], 2),
+            Ok("B3/S08".parse().unwrap())
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "neighbor counts go up to 8")]
+    fn new_panics_on_a_count_above_eight() {
+        Rule::new(&[16], &[2, 3]);
+    }
+}
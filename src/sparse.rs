@@ -0,0 +1,188 @@
+use crate::game_of_life::Cell;
+use crate::rule::Rule;
+use std::collections::{HashMap, HashSet};
+
+// An unbounded universe that only stores its live cells, for a handful of
+// patterns in a huge (or infinite) space. Each step only looks at live cells
+// and their neighbors, so rules that give birth on 0 neighbors can't be
+// represented and behave as if they didn't. Only two-state rules are
+// supported.
+pub struct SparseUniverse {
+    live: HashSet<(i64, i64)>,
+    generation: u64,
+    rule: Rule,
+}
+
+impl SparseUniverse {
+    pub fn new() -> SparseUniverse {
+        SparseUniverse {
+            live: HashSet::new(),
+            generation: 0,
+            rule: Rule::default(),
+        }
+    }
+
+    pub fn get_cell(&self, row: i64, col: i64) -> Cell {
+        if self.live.contains(&(col, row)) {
+            Cell::Alive
+        } else {
+            Cell::Dead
+        }
+    }
+
+    pub fn set_cell(&mut self, row: i64, col: i64, state: Cell) {
+        match state {
+            Cell::Alive => self.live.insert((col, row)),
+            _ => self.live.remove(&(col, row)),
+        };
+    }
+
+    pub fn toggle_cell(&mut self, row: i64, col: i64) {
+        if !self.live.remove(&(col, row)) {
+            self.live.insert((col, row));
+        }
+    }
+
+    // stamps live (row, col) cells translated to the origin
+    pub fn insert_pattern(&mut self, cells: &[(u32, u32)], origin_row: i64, origin_col: i64) {
+        for &(row, col) in cells {
+            self.live
+                .insert((origin_col + col as i64, origin_row + row as i64));
+        }
+    }
+
+    pub fn population(&self) -> usize {
+        self.live.len()
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    pub fn set_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+    }
+
+    // live cells as (col, row), in no particular order
+    pub fn live_cells(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        self.live.iter().cloned()
+    }
+
+    // steps one generation, returning whether any cell changed
+    pub fn update(&mut self) -> bool {
+        let mut neighbor_counts: HashMap<(i64, i64), u8> = HashMap::new();
+        for &(col, row) in self.live.iter() {
+            // live cells with no neighbors still have to be considered
+            neighbor_counts.entry((col, row)).or_insert(0);
+            for delta_row in [-1, 0, 1].iter().cloned() {
+                for delta_col in [-1, 0, 1].iter().cloned() {
+                    if delta_row == 0 && delta_col == 0 {
+                        continue;
+                    }
+                    *neighbor_counts
+                        .entry((col + delta_col, row + delta_row))
+                        .or_insert(0) += 1;
+                }
+            }
+        }
+
+        let next: HashSet<(i64, i64)> = neighbor_counts
+            .into_iter()
+            .filter(|&(cell, live_neighbors)| {
+                if self.live.contains(&cell) {
+                    self.rule.survives(live_neighbors)
+                } else {
+                    self.rule.is_born(live_neighbors)
+                }
+            })
+            .map(|(cell, _)| cell)
+            .collect();
+
+        let changed = next != self.live;
+        self.live = next;
+        self.generation += 1;
+        changed
+    }
+}
+
+impl Default for SparseUniverse {
+    fn default() -> SparseUniverse {
+        SparseUniverse::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_of_life::{Boundary, Universe};
+    use crate::patterns;
+
+    fn sorted(cells: impl Iterator<Item = (i64, i64)>) -> Vec<(i64, i64)> {
+        let mut cells: Vec<(i64, i64)> = cells.collect();
+        cells.sort_unstable();
+        cells
+    }
+
+    #[test]
+    fn glider_travels_diagonally() {
+        let mut sparse = SparseUniverse::new();
+        sparse.insert_pattern(&patterns::glider(), -1, -1);
+        let start = sorted(sparse.live_cells());
+        for generation in 1..=400 {
+            sparse.update();
+            assert_eq!(sparse.population(), 5, "gen {}", generation);
+        }
+        // a cell down and to the right every four generations, with nothing
+        // in the way
+        let moved: Vec<(i64, i64)> = start
+            .iter()
+            .map(|&(col, row)| (col + 100, row + 100))
+            .collect();
+        assert_eq!(sorted(sparse.live_cells()), moved);
+        assert_eq!(sparse.generation(), 400);
+    }
+
+    #[test]
+    fn matches_a_dense_board_away_from_its_edges() {
+        for &rule in &[Rule::conway(), Rule::highlife()] {
+            let soup = Universe::new_random(16, 16, 0.5, 77);
+            let mut dense = Universe::new_random(128, 128, 0.0, 0);
+            dense.set_rule(rule);
+            dense.set_boundary(Boundary::Dead);
+            let cells: Vec<(u32, u32)> = soup.live_cells().map(|(col, row)| (row, col)).collect();
+            dense.insert_pattern(&cells, 56, 56);
+            let mut sparse = SparseUniverse::new();
+            sparse.set_rule(rule);
+            sparse.insert_pattern(&cells, 56, 56);
+
+            // nothing can travel faster than a cell a generation, so in 40
+            // generations nothing reaches the dense board's edges
+            for _ in 0..40 {
+                assert_eq!(sparse.update(), dense.update());
+                let dense_cells = dense
+                    .live_cells()
+                    .map(|(col, row)| (col as i64, row as i64));
+                assert_eq!(sorted(sparse.live_cells()), sorted(dense_cells));
+            }
+        }
+    }
+
+    #[test]
+    fn cell_access() {
+        let mut sparse = SparseUniverse::default();
+        sparse.set_cell(-5, 7, Cell::Alive);
+        sparse.toggle_cell(3, 3);
+        sparse.toggle_cell(3, 3);
+        assert_eq!(sparse.get_cell(-5, 7), Cell::Alive);
+        assert_eq!(sparse.get_cell(3, 3), Cell::Dead);
+        assert_eq!(sparse.population(), 1);
+        // a lone cell dies
+        assert!(sparse.update());
+        assert_eq!(sparse.population(), 0);
+        assert!(!sparse.update());
+    }
+}
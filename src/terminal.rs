@@ -0,0 +1,66 @@
+use crate::game_of_life::Universe;
+use std::io::{self, Write};
+use std::thread;
+use std::time::Duration;
+
+const CLEAR_SCREEN: &str = "\x1b[2J";
+const CURSOR_HOME: &str = "\x1b[H";
+const CLEAR_TO_END_OF_LINE: &str = "\x1b[K";
+
+// Draws the board in the terminal with ANSI escapes, redrawing over itself
+// `updates_per_second` times a second, for when there's no display. Runs
+// until interrupted.
+pub fn run(mut universe: Universe, updates_per_second: f64) -> io::Result<()> {
+    let frame_time = Duration::from_secs_f64(1.0 / updates_per_second.max(0.1));
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    write!(out, "{}", CLEAR_SCREEN)?;
+    loop {
+        write_frame(&mut out, &universe)?;
+        thread::sleep(frame_time);
+        universe.update();
+    }
+}
+
+// one redraw: the board from the top left, then the counters
+fn write_frame<W: Write>(out: &mut W, universe: &Universe) -> io::Result<()> {
+    write!(
+        out,
+        "{}{}\ngen: {}   pop: {}{}",
+        CURSOR_HOME,
+        universe.to_ascii_frame(),
+        universe.generation(),
+        universe.population(),
+        CLEAR_TO_END_OF_LINE
+    )?;
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(universe: &Universe) -> String {
+        let mut out = Vec::new();
+        write_frame(&mut out, universe).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn draws_over_the_last_frame() {
+        let mut universe = Universe::from_plaintext(".....\n..O..\n..O..\n..O..\n.....").unwrap();
+        let first = frame(&universe);
+        assert!(first.starts_with(CURSOR_HOME));
+        assert!(first.ends_with(CLEAR_TO_END_OF_LINE));
+        assert!(first.contains(&universe.to_ascii_frame()));
+        assert!(first.contains("gen: 0   pop: 3"));
+        // no clearing of the whole screen, which would flicker
+        assert!(!first.contains(CLEAR_SCREEN));
+
+        universe.update();
+        let second = frame(&universe);
+        assert!(second.contains(&universe.to_ascii_frame()));
+        assert!(second.contains("gen: 1   pop: 3"));
+        assert_ne!(first, second);
+    }
+}
@@ -1,29 +1,228 @@
-pub struct TimingBuffer {
-    buffer: Vec<f64>,
+use std::collections::VecDeque;
+use std::fmt;
+use std::iter::Sum;
+
+// The last `size` samples, oldest first. Usually frame times in seconds, but
+// anything that converts to f64 will do, such as a population per frame.
+pub struct TimingBuffer<T = f64> {
+    buffer: VecDeque<T>,
     size: usize,
 }
 
-impl TimingBuffer {
-    pub fn new(size: usize) -> TimingBuffer {
+impl<T: Copy + Into<f64> + Sum> TimingBuffer<T> {
+    pub fn new(size: usize) -> TimingBuffer<T> {
         TimingBuffer {
-            buffer: Vec::with_capacity(size),
+            buffer: VecDeque::with_capacity(size),
             size,
         }
     }
 
-    pub fn avg(&self) -> f64 {
-        let avg = 1.0 / (self.buffer.iter().sum::<f64>() / self.buffer.len() as f64);
-        avg
+    /// Mean of the samples, seconds per frame for frame times, or 0.0
+    /// before there's anything to average.
+    ///
+    /// ```
+    /// use life::timing_buffer::TimingBuffer;
+    ///
+    /// let fps: TimingBuffer = TimingBuffer::new(10);
+    /// assert_eq!(fps.avg_frame_time(), 0.0);
+    ///
+    /// // any sample type converting to f64 works
+    /// let mut population = TimingBuffer::new(4);
+    /// for &count in &[10u32, 20, 30, 40] {
+    ///     population.add_time(count);
+    /// }
+    /// assert_eq!(population.avg_frame_time(), 25.0);
+    /// ```
+    pub fn avg_frame_time(&self) -> f64 {
+        if self.buffer.is_empty() {
+            return 0.0;
+        }
+        let total: f64 = self.buffer.iter().cloned().sum::<T>().into();
+        total / self.buffer.len() as f64
+    }
+
+    /// Frames per second over the buffer, the reciprocal of `avg_frame_time`,
+    /// or 0.0 before there's anything to average.
+    ///
+    /// ```
+    /// use life::timing_buffer::TimingBuffer;
+    ///
+    /// let mut fps = TimingBuffer::new(4);
+    /// assert_eq!(fps.avg_fps(), 0.0);
+    /// for &time in &[0.02, 0.02, 0.03, 0.03] {
+    ///     fps.add_time(time);
+    /// }
+    /// assert_eq!(fps.avg_frame_time(), 0.025);
+    /// assert_eq!(fps.avg_fps(), 40.0);
+    /// ```
+    pub fn avg_fps(&self) -> f64 {
+        let frame_time = self.avg_frame_time();
+        if frame_time <= 0.0 {
+            return 0.0;
+        }
+        1.0 / frame_time
+    }
+
+    /// Smallest sample, or 0.0 for an empty buffer. Like `avg_frame_time`
+    /// this is a time, not a rate.
+    ///
+    /// ```
+    /// use life::timing_buffer::TimingBuffer;
+    ///
+    /// let mut buffer = TimingBuffer::new(3);
+    /// assert_eq!(buffer.min(), 0.0);
+    /// for &time in &[0.02, 0.01, 0.04, 0.03] {
+    ///     buffer.add_time(time);
+    /// }
+    /// assert_eq!(buffer.min(), 0.01);
+    /// assert_eq!(buffer.max(), 0.04);
+    /// assert_eq!(buffer.median(), 0.03);
+    /// ```
+    pub fn min(&self) -> f64 {
+        if self.buffer.is_empty() {
+            return 0.0;
+        }
+        self.samples().fold(f64::INFINITY, f64::min)
     }
 
-    pub fn add_time(&mut self, timing: f64) {
+    /// Largest sample, or 0.0 for an empty buffer.
+    pub fn max(&self) -> f64 {
         if self.buffer.is_empty() {
-            for _i in 1..self.size {
-                self.buffer.push(timing);
+            return 0.0;
+        }
+        self.samples().fold(f64::NEG_INFINITY, f64::max)
+    }
+
+    /// Middle sample, the mean of the two middle ones for an even count, or
+    /// 0.0 for an empty buffer.
+    pub fn median(&self) -> f64 {
+        self.percentile(0.5)
+    }
+
+    /// The `p`th percentile of the samples, `p` running from 0.0 to 1.0,
+    /// interpolating between the two nearest samples; 0.0 for an empty
+    /// buffer.
+    ///
+    /// ```
+    /// use life::timing_buffer::TimingBuffer;
+    ///
+    /// let mut buffer = TimingBuffer::new(5);
+    /// for &time in &[0.05, 0.01, 0.04, 0.02, 0.03, 0.05] {
+    ///     buffer.add_time(time);
+    /// }
+    /// assert_eq!(buffer.percentile(0.0), 0.01);
+    /// assert_eq!(buffer.percentile(0.5), 0.03);
+    /// assert_eq!(buffer.percentile(1.0), 0.05);
+    /// assert!((buffer.percentile(0.875) - 0.045).abs() < 1e-12);
+    /// ```
+    pub fn percentile(&self, p: f64) -> f64 {
+        let sorted = self.sorted();
+        if sorted.is_empty() {
+            return 0.0;
+        }
+        let rank = p.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+        let (below, above) = (rank.floor() as usize, rank.ceil() as usize);
+        sorted[below] + (sorted[above] - sorted[below]) * (rank - below as f64)
+    }
+
+    fn samples(&self) -> impl Iterator<Item = f64> + '_ {
+        self.buffer.iter().map(|&sample| sample.into())
+    }
+
+    // a sorted copy of the samples, leaving the ring as it is
+    fn sorted(&self) -> Vec<f64> {
+        let mut sorted: Vec<f64> = self.samples().collect();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        sorted
+    }
+
+    /// Number of samples held; the first sample fills the whole buffer.
+    ///
+    /// ```
+    /// use life::timing_buffer::TimingBuffer;
+    ///
+    /// let mut fps = TimingBuffer::new(100);
+    /// assert_eq!(fps.len(), 0);
+    /// fps.add_time(0.016);
+    /// assert_eq!(fps.len(), 100);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    /// Drops every sample, so the next one starts the buffer afresh.
+    ///
+    /// ```
+    /// use life::timing_buffer::TimingBuffer;
+    ///
+    /// let mut fps = TimingBuffer::new(10);
+    /// fps.add_time(0.5);
+    /// fps.reset();
+    /// assert!(fps.is_empty());
+    /// assert_eq!(fps.avg_fps(), 0.0);
+    /// ```
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+    }
+
+    /// Adds a sample, dropping the oldest one.
+    ///
+    /// ```
+    /// use life::timing_buffer::TimingBuffer;
+    ///
+    /// let mut buffer = TimingBuffer::new(4);
+    /// for i in 1..=10_000 {
+    ///     buffer.add_time(i as f64);
+    /// }
+    /// // only 9997 to 10000 are left
+    /// assert_eq!(buffer.len(), 4);
+    /// assert_eq!(buffer.avg_frame_time(), 9998.5);
+    /// ```
+    pub fn add_time(&mut self, timing: T) {
+        if self.buffer.is_empty() {
+            for _i in 0..self.size {
+                self.buffer.push_back(timing);
             }
         } else {
-            self.buffer.remove(0);
-            self.buffer.push(timing);
+            self.buffer.pop_front();
+            self.buffer.push_back(timing);
         }
     }
 }
+
+// frames per second for a frame time, 0.0 for no time at all
+fn rate(frame_time: f64) -> f64 {
+    if frame_time > 0.0 {
+        1.0 / frame_time
+    } else {
+        0.0
+    }
+}
+
+/// Average, slowest and fastest frame rates, treating the samples as frame
+/// times.
+///
+/// ```
+/// use life::timing_buffer::TimingBuffer;
+///
+/// let mut fps = TimingBuffer::new(4);
+/// for &time in &[0.02, 0.02, 0.025, 0.05] {
+///     fps.add_time(time);
+/// }
+/// assert_eq!(fps.to_string(), "fps: 34.8 (min 20.0 / max 50.0)");
+/// ```
+impl<T: Copy + Into<f64> + Sum> fmt::Display for TimingBuffer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "fps: {:.1} (min {:.1} / max {:.1})",
+            self.avg_fps(),
+            rate(self.max()),
+            rate(self.min())
+        )
+    }
+}
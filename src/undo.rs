@@ -0,0 +1,179 @@
+use crate::game_of_life::{Cell, Universe};
+use std::collections::VecDeque;
+
+// Undo for edits made by hand, as opposed to stepping the simulation back.
+// An edit is everything between `begin` and `end`, like one drag of the
+// mouse, and undoing it puts back the cells it recorded.
+pub struct UndoStack {
+    // (row, col, state before) of every recorded cell of each edit, oldest
+    // edit first
+    edits: VecDeque<Vec<(u32, u32, Cell)>>,
+    // the edit in progress
+    current: Vec<(u32, u32, Cell)>,
+    // edits kept before the oldest are forgotten
+    capacity: usize,
+}
+
+impl UndoStack {
+    pub fn new(capacity: usize) -> UndoStack {
+        UndoStack {
+            edits: VecDeque::with_capacity(capacity),
+            current: Vec::new(),
+            capacity,
+        }
+    }
+
+    // starts a new edit, finishing any that's still open
+    pub fn begin(&mut self) {
+        self.end();
+    }
+
+    // saves the state of (row, col) before the edit in progress changes it
+    pub fn record(&mut self, universe: &Universe, row: u32, col: u32) {
+        self.current.push((row, col, universe.get_cell(row, col)));
+    }
+
+    /// Sets a cell as part of the edit in progress, remembering what it was.
+    ///
+    /// ```
+    /// use life::undo::UndoStack;
+    /// use life::{Cell, Universe};
+    ///
+    /// let mut universe = Universe::from_plaintext("...\n.O.\n...").unwrap();
+    /// let original = universe.to_plaintext();
+    /// let mut undo = UndoStack::new(10);
+    ///
+    /// undo.begin();
+    /// undo.set_cell(&mut universe, 0, 0, Cell::Alive);
+    /// undo.set_cell(&mut universe, 1, 1, Cell::Dead);
+    /// undo.end();
+    /// assert_eq!(universe.to_plaintext(), "O..\n...\n...\n");
+    ///
+    /// assert!(undo.undo(&mut universe));
+    /// assert_eq!(universe.to_plaintext(), original);
+    /// assert!(!undo.undo(&mut universe));
+    /// ```
+    pub fn set_cell(&mut self, universe: &mut Universe, row: u32, col: u32, state: Cell) {
+        self.record(universe, row, col);
+        universe.set_cell(row, col, state);
+    }
+
+    // closes the edit in progress; edits that recorded nothing are dropped
+    pub fn end(&mut self) {
+        if self.current.is_empty() {
+            return;
+        }
+        if self.edits.len() == self.capacity {
+            self.edits.pop_front();
+        }
+        if self.capacity > 0 {
+            self.edits.push_back(std::mem::take(&mut self.current));
+        } else {
+            self.current.clear();
+        }
+    }
+
+    // reverts the last edit, returning false when there's nothing to undo
+    pub fn undo(&mut self, universe: &mut Universe) -> bool {
+        self.end();
+        match self.edits.pop_back() {
+            Some(edit) => {
+                // newest first, so a cell recorded twice ends up as it was
+                // before the first change
+                for &(row, col, state) in edit.iter().rev() {
+                    universe.set_cell(row, col, state);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.edits.clear();
+        self.current.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board() -> Universe {
+        Universe::from_plaintext("....\n....\n....").unwrap()
+    }
+
+    // one edit setting every cell in `cells` alive
+    fn edit(undo: &mut UndoStack, universe: &mut Universe, cells: &[(u32, u32)]) {
+        undo.begin();
+        for &(row, col) in cells {
+            undo.set_cell(universe, row, col, Cell::Alive);
+        }
+        undo.end();
+    }
+
+    #[test]
+    fn undoes_edits_newest_first() {
+        let mut universe = board();
+        let mut undo = UndoStack::new(10);
+        edit(&mut undo, &mut universe, &[(0, 0), (0, 1)]);
+        let after_first = universe.to_plaintext();
+        edit(&mut undo, &mut universe, &[(2, 3)]);
+
+        assert!(undo.undo(&mut universe));
+        assert_eq!(universe.to_plaintext(), after_first);
+        assert!(undo.undo(&mut universe));
+        assert_eq!(universe.to_plaintext(), board().to_plaintext());
+        assert!(!undo.undo(&mut universe));
+    }
+
+    #[test]
+    fn keeps_only_the_newest_edits() {
+        let mut universe = board();
+        let mut undo = UndoStack::new(2);
+        for col in 0..4 {
+            edit(&mut undo, &mut universe, &[(1, col)]);
+        }
+        assert!(undo.undo(&mut universe));
+        assert!(undo.undo(&mut universe));
+        assert!(!undo.undo(&mut universe));
+        // the two oldest edits can't be taken back any more
+        assert_eq!(universe.to_plaintext(), "....\nOO..\n....\n");
+    }
+
+    #[test]
+    fn zero_capacity_keeps_nothing() {
+        let mut universe = board();
+        let mut undo = UndoStack::new(0);
+        edit(&mut undo, &mut universe, &[(0, 0)]);
+        assert!(!undo.undo(&mut universe));
+        assert_eq!(universe.population(), 1);
+    }
+
+    #[test]
+    fn a_cell_changed_twice_goes_back_to_its_first_state() {
+        let mut universe = board();
+        let mut undo = UndoStack::new(10);
+        undo.begin();
+        undo.set_cell(&mut universe, 1, 1, Cell::Alive);
+        undo.set_cell(&mut universe, 1, 1, Cell::Dead);
+        undo.set_cell(&mut universe, 1, 1, Cell::Alive);
+        // an edit still open is closed by undoing it
+        assert!(undo.undo(&mut universe));
+        assert_eq!(universe.get_cell(1, 1), Cell::Dead);
+    }
+
+    #[test]
+    fn empty_edits_and_cleared_stacks_have_nothing_to_undo() {
+        let mut universe = board();
+        let mut undo = UndoStack::new(10);
+        undo.begin();
+        undo.end();
+        assert!(!undo.undo(&mut universe));
+
+        edit(&mut undo, &mut universe, &[(0, 0)]);
+        undo.clear();
+        assert!(!undo.undo(&mut universe));
+        assert_eq!(universe.population(), 1);
+    }
+}
@@ -0,0 +1,125 @@
+use crate::game_of_life::Universe;
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+
+// the animation frame callback, which has to hold on to itself to ask for
+// the next frame
+type FrameCallback = Rc<RefCell<Option<Closure<dyn FnMut()>>>>;
+
+// the same fraction of live cells the window's random boards start with
+const RANDOM_DENSITY: f64 = 0.3;
+const BG_COLOR: &str = "#ffffff";
+const FG_COLOR: &str = "#000000";
+
+// A board drawn on an HTML5 canvas, one cell_size x cell_size square per
+// cell. The canvas is resized to fit the board.
+#[wasm_bindgen]
+pub struct WebLife {
+    universe: Universe,
+    context: CanvasRenderingContext2d,
+    cell_size: f64,
+}
+
+#[wasm_bindgen]
+impl WebLife {
+    // a random board drawn on the canvas with id `canvas_id`
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        canvas_id: &str,
+        width: u32,
+        height: u32,
+        cell_size: f64,
+        seed: u32,
+    ) -> Result<WebLife, JsValue> {
+        let document = web_sys::window()
+            .and_then(|window| window.document())
+            .ok_or_else(|| JsValue::from_str("no document"))?;
+        let canvas: HtmlCanvasElement = document
+            .get_element_by_id(canvas_id)
+            .ok_or_else(|| JsValue::from_str(&format!("no element '{}'", canvas_id)))?
+            .dyn_into()?;
+        canvas.set_width((width as f64 * cell_size) as u32);
+        canvas.set_height((height as f64 * cell_size) as u32);
+        let context = canvas
+            .get_context("2d")?
+            .ok_or_else(|| JsValue::from_str("no 2d context"))?
+            .dyn_into()?;
+
+        let universe = Universe::new_random(width, height, RANDOM_DENSITY, seed as u64);
+        Ok(WebLife {
+            universe,
+            context,
+            cell_size,
+        })
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.universe.generation()
+    }
+
+    pub fn population(&self) -> usize {
+        self.universe.population()
+    }
+
+    pub fn toggle_cell(&mut self, row: u32, col: u32) {
+        self.universe.toggle_cell(row, col);
+    }
+
+    // one generation, then a redraw
+    pub fn tick(&mut self) {
+        self.universe.update();
+        self.draw();
+    }
+
+    pub fn draw(&self) {
+        let size = self.cell_size;
+        let context = &self.context;
+        context.set_fill_style(&JsValue::from_str(BG_COLOR));
+        context.fill_rect(
+            0.0,
+            0.0,
+            self.universe.width() as f64 * size,
+            self.universe.height() as f64 * size,
+        );
+        context.set_fill_style(&JsValue::from_str(FG_COLOR));
+        for (col, row) in self.universe.live_cells() {
+            context.fill_rect(col as f64 * size, row as f64 * size, size, size);
+        }
+    }
+}
+
+// Runs a random board on the canvas with id `canvas_id`, stepping it once per
+// requestAnimationFrame until the page goes away.
+#[wasm_bindgen]
+pub fn start(
+    canvas_id: &str,
+    width: u32,
+    height: u32,
+    cell_size: f64,
+    seed: u32,
+) -> Result<(), JsValue> {
+    let mut life = WebLife::new(canvas_id, width, height, cell_size, seed)?;
+    life.draw();
+
+    let frame: FrameCallback = Rc::new(RefCell::new(None));
+    let next = frame.clone();
+    *frame.borrow_mut() = Some(Closure::wrap(Box::new(move || {
+        life.tick();
+        if let Some(callback) = next.borrow().as_ref() {
+            request_animation_frame(callback);
+        }
+    }) as Box<dyn FnMut()>));
+    if let Some(callback) = frame.borrow().as_ref() {
+        request_animation_frame(callback);
+    }
+    Ok(())
+}
+
+fn request_animation_frame(callback: &Closure<dyn FnMut()>) {
+    if let Some(window) = web_sys::window() {
+        let _ = window.request_animation_frame(callback.as_ref().unchecked_ref());
+    }
+}
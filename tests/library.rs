@@ -0,0 +1,33 @@
+// The library on its own, the way a crate depending on `life` would use it,
+// without anything from the viewer binary.
+use life::formats::Format;
+use life::patterns;
+use life::rule::Rule;
+use life::{Cell, Universe};
+
+#[test]
+fn glider_runs_through_the_public_api() {
+    // a density of 0 is an empty board
+    let mut universe = Universe::new_random(10, 10, 0.0, 0);
+    universe.insert_pattern(&patterns::glider(), 0, 0);
+    for _ in 0..4 {
+        universe.update();
+    }
+    assert_eq!(universe.generation(), 4);
+    assert_eq!(universe.population(), 5);
+    assert_eq!(universe.bounding_box(), Some((1, 1, 3, 3)));
+    assert_eq!(universe.get_cell(3, 3), Cell::Alive);
+}
+
+#[test]
+fn patterns_round_trip_through_the_formats() {
+    let rle = "x = 3, y = 1, rule = B36/S23\n3o!\n";
+    let universe = Universe::from_pattern(rle, Format::sniff(rle), 5, 5).unwrap();
+    assert_eq!((universe.width(), universe.height()), (5, 5));
+    assert_eq!(universe.rule(), Rule::highlife());
+    assert_eq!(universe.population(), 3);
+
+    let copy = Universe::from_rle(&universe.to_rle()).unwrap();
+    assert_eq!(copy.rule(), Rule::highlife());
+    assert_eq!(copy.to_plaintext(), "OOO\n");
+}
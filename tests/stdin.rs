@@ -0,0 +1,47 @@
+// Patterns piped into the binary with `--stdin`, with no file name to go by,
+// so the format has to be guessed from the text.
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn run_headless(pattern: &str, args: &[&str]) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_life"))
+        .args([
+            "--stdin",
+            "--headless",
+            "--cells-x",
+            "20",
+            "--cells-y",
+            "20",
+        ])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(pattern.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn glider_rle_from_stdin() {
+    let output = run_headless(
+        "#N Glider\nx = 3, y = 3, rule = B3/S23\nbob$2bo$3o!\n",
+        &["--steps", "8"],
+    );
+    assert_eq!(output, "generation: 8 population: 5\n");
+}
+
+#[test]
+fn plaintext_from_stdin() {
+    let output = run_headless("!Name: Block\nOO\nOO\n", &["--steps", "3"]);
+    assert_eq!(output, "generation: 3 population: 4\n");
+    let output = run_headless("***\n", &["--steps", "1"]);
+    assert_eq!(output, "generation: 1 population: 3\n");
+}